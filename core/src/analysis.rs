@@ -0,0 +1,134 @@
+//! Control flow analysis of a ROM, telling code apart from the data around it.
+//!
+//! Starting at `0x200`, the analysis follows jumps, calls, skips and returns to
+//! find every instruction the program can reach. Along the way it keeps track of
+//! `LD I, NNN` so the bytes a later `DRW`, `FX33`, `FX55`, `FX65` or `F002` uses
+//! are known to be data.
+
+use crate::constants::PC_DEFAULT_START;
+use crate::instruction::{decode_at, Instruction};
+use std::collections::BTreeSet;
+
+/// What a byte of the program was found to be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ByteKind {
+    /// Not reached by the analysis
+    Unknown,
+    Code,
+    Data,
+}
+
+pub(crate) struct Analysis {
+    pub kinds: Vec<ByteKind>,
+    /// Offsets of the instructions found, as opposed to their operand bytes
+    pub instructions: BTreeSet<usize>,
+    /// Offsets where a block of data starts, as referenced by `I`
+    pub data: BTreeSet<usize>,
+    /// The blocks of data that are drawn
+    pub sprites: BTreeSet<usize>,
+}
+
+impl Analysis {
+    pub fn new(program: &[u8]) -> Self {
+        let mut analysis = Analysis {
+            kinds: vec![ByteKind::Unknown; program.len()],
+            instructions: BTreeSet::new(),
+            data: BTreeSet::new(),
+            sprites: BTreeSet::new(),
+        };
+
+        // Offsets still to follow, along with the value of I when getting there
+        let mut pending = vec![(0, None)];
+        while let Some((offset, i)) = pending.pop() {
+            analysis.follow(program, offset, i, &mut pending);
+        }
+        analysis
+    }
+
+    /// Walks straight-line code from `offset`, queuing up the branches it meets
+    fn follow(
+        &mut self,
+        program: &[u8],
+        mut offset: usize,
+        mut i: Option<u16>,
+        pending: &mut Vec<(usize, Option<u16>)>,
+    ) {
+        let to_offset = |addr: u16| (addr as usize).checked_sub(PC_DEFAULT_START);
+
+        while offset < program.len() && !self.instructions.contains(&offset) {
+            let instruction = decode_at(program, offset);
+            let next = offset + instruction.size();
+            let fits = self
+                .kinds
+                .get(offset..next)
+                .is_some_and(|kinds| !kinds.contains(&ByteKind::Code));
+            if !fits || matches!(instruction, Instruction::Unknown(_)) {
+                return;
+            }
+            self.kinds[offset..next].fill(ByteKind::Code);
+            self.instructions.insert(offset);
+
+            match instruction {
+                Instruction::Jp(addr) | Instruction::JpV0(addr) => {
+                    // The target of JP V0 is only known for V0 = 0, which is
+                    // usually the first entry of a jump table
+                    pending.extend(to_offset(addr).map(|target| (target, i)));
+                    return;
+                }
+                Instruction::Ret | Instruction::Exit => return,
+                Instruction::Call(addr) => {
+                    pending.extend(to_offset(addr).map(|target| (target, i)));
+                }
+                Instruction::SeImm { .. }
+                | Instruction::SneImm { .. }
+                | Instruction::SeReg { .. }
+                | Instruction::SneReg { .. }
+                | Instruction::Skp(_)
+                | Instruction::Sknp(_) => {
+                    let skipped = next + decode_at(program, next).size();
+                    pending.push((skipped, i));
+                }
+                Instruction::LdI(addr) | Instruction::LdILong(addr) => i = Some(addr),
+                Instruction::AddI(_) | Instruction::LdF(_) | Instruction::LdHf(_) => i = None,
+                // DXY0 draws 16x16 sprites
+                Instruction::Drw { n, .. } => {
+                    if let Some(start) = self.mark_data(i, if n == 0 { 32 } else { n as usize }) {
+                        self.sprites.insert(start);
+                    }
+                }
+                Instruction::LdB(_) => {
+                    self.mark_data(i, 3);
+                }
+                Instruction::Audio => {
+                    self.mark_data(i, 16);
+                }
+                Instruction::SaveRange { x, y } | Instruction::LoadRange { x, y } => {
+                    self.mark_data(i, x.abs_diff(y) as usize + 1);
+                }
+                // I may move past the data depending on the quirks
+                Instruction::LdIVx(x) | Instruction::LdVxI(x) => {
+                    self.mark_data(i, x as usize + 1);
+                    i = None;
+                }
+                _ => (),
+            }
+            offset = next;
+        }
+    }
+
+    /// Marks `length` bytes from I as data, unless they are code. Returns where the
+    /// data starts if it's in the program.
+    fn mark_data(&mut self, i: Option<u16>, length: usize) -> Option<usize> {
+        let start = i
+            .and_then(|addr| (addr as usize).checked_sub(PC_DEFAULT_START))
+            .filter(|&start| start < self.kinds.len())?;
+        let end = (start + length).min(self.kinds.len());
+        for kind in &mut self.kinds[start..end] {
+            if *kind == ByteKind::Unknown {
+                *kind = ByteKind::Data;
+            }
+        }
+        self.data.insert(start);
+        Some(start)
+    }
+}
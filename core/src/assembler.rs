@@ -0,0 +1,374 @@
+//! Assembler for the Cowgod-style mnemonics the [`Disassembler`] writes, so its
+//! listings can be edited and turned back into a ROM.
+//!
+//! Each line holds an optional `label:`, an instruction or directive and an
+//! optional `; comment`:
+//!
+//! ```text
+//! SPEED EQU 2
+//!
+//! start:
+//!     LD I, sprite        ; 8x3 block
+//!     DRW v0, v1, 3
+//!     ADD v0, SPEED
+//!     JP start
+//! sprite:
+//!     db 0xff, 0xff, 0xff
+//! ```
+//!
+//! Addresses are hexadecimal, written without a prefix like the disassembler does,
+//! and every other number is decimal. `0x`, `$` or `#` mark hexadecimal and `%`
+//! binary, as does `0b` outside of addresses. `db` and `dw` lay out bytes and
+//! big-endian words, and `NAME EQU value` defines a constant.
+//!
+//! [`Disassembler`]: crate::Disassembler
+
+use crate::constants::PC_DEFAULT_START;
+use crate::error::AsmError;
+use crate::instruction::{encode_bytes, Instruction};
+use std::collections::HashMap;
+
+const MNEMONICS: &[&str] = &[
+    "SYS", "CLS", "RET", "SCD", "SCU", "SCR", "SCL", "EXIT", "LOW", "HIGH", "JP", "CALL", "SE",
+    "SNE", "SAVE", "LOAD", "LD", "ADD", "OR", "AND", "XOR", "SUB", "SHR", "SUBN", "SHL", "RND",
+    "DRW", "SKP", "SKNP", "PLANE", "AUDIO", "PITCH",
+];
+
+/// A line stripped of its label and comment
+struct Statement<'a> {
+    line: usize,
+    mnemonic: &'a str,
+    operands: Vec<&'a str>,
+}
+
+/// How an operand is written
+enum Operand<'a> {
+    V(u8),
+    I,
+    IndirectI,
+    Dt,
+    St,
+    K,
+    F,
+    Hf,
+    B,
+    R,
+    /// `LONG NNNN`, the address of `F000 NNNN`
+    Long(&'a str),
+    /// A number or a symbol
+    Value(&'a str),
+}
+
+/// What a number without prefix is read as
+#[derive(Debug, Clone, Copy)]
+enum Radix {
+    Decimal,
+    Address,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Assembler {}
+
+impl Assembler {
+    /// Assembles `source` into a ROM to be loaded at `0x200`
+    pub fn assemble(&self, source: &str) -> Result<Vec<u8>, AsmError> {
+        let mut symbols = HashMap::new();
+        let mut statements = Vec::new();
+
+        // Labels can be used before they are defined, so their addresses are all
+        // worked out before encoding anything
+        let mut addr = PC_DEFAULT_START;
+        for (index, text) in source.lines().enumerate() {
+            let line = index + 1;
+            let mut text = text.split(';').next().unwrap_or_default().trim();
+            if let Some((label, rest)) = text.split_once(':') {
+                define(&mut symbols, label.trim(), addr, line)?;
+                text = rest.trim();
+            }
+            if text.is_empty() {
+                continue;
+            }
+
+            let (mnemonic, rest) = text
+                .split_once(char::is_whitespace)
+                .map_or((text, ""), |(mnemonic, rest)| (mnemonic, rest.trim()));
+            if let Some((directive, value)) = rest.split_once(char::is_whitespace) {
+                if directive.eq_ignore_ascii_case("EQU") {
+                    let value = resolve(&symbols, line, value.trim(), Radix::Decimal, 0xffff)?;
+                    define(&mut symbols, mnemonic, value, line)?;
+                    continue;
+                }
+            }
+
+            let operands = if rest.is_empty() {
+                Vec::new()
+            } else {
+                rest.split(',').map(str::trim).collect()
+            };
+            let statement = Statement {
+                line,
+                mnemonic,
+                operands,
+            };
+            addr += size(&statement);
+            statements.push(statement);
+        }
+
+        let mut rom = Vec::new();
+        for statement in &statements {
+            let line = statement.line;
+            match statement.mnemonic.to_ascii_lowercase().as_str() {
+                "db" | "dw" if statement.operands.is_empty() => {
+                    return Err(AsmError::InvalidOperands { line })
+                }
+                "db" => {
+                    for operand in &statement.operands {
+                        rom.push(resolve(&symbols, line, operand, Radix::Decimal, 0xff)? as u8);
+                    }
+                }
+                "dw" => {
+                    for operand in &statement.operands {
+                        let word = resolve(&symbols, line, operand, Radix::Decimal, 0xffff)?;
+                        rom.extend_from_slice(&(word as u16).to_be_bytes());
+                    }
+                }
+                _ => rom.extend(encode_bytes(&self.instruction(statement, &symbols)?)),
+            }
+        }
+        Ok(rom)
+    }
+
+    fn instruction(
+        &self,
+        statement: &Statement,
+        symbols: &HashMap<String, usize>,
+    ) -> Result<Instruction, AsmError> {
+        use Operand::*;
+
+        let line = statement.line;
+        let addr = |text| resolve(symbols, line, text, Radix::Address, 0xfff).map(|a| a as u16);
+        let byte = |text| resolve(symbols, line, text, Radix::Decimal, 0xff).map(|b| b as u8);
+        let nibble = |text| resolve(symbols, line, text, Radix::Decimal, 0xf).map(|n| n as u8);
+
+        let mnemonic = statement.mnemonic.to_ascii_uppercase();
+        let operands: Vec<Operand> = statement
+            .operands
+            .iter()
+            .map(|text| operand(text))
+            .collect();
+        let instruction = match (mnemonic.as_str(), operands.as_slice()) {
+            ("SYS", [Value(a)]) => Instruction::Sys(addr(a)?),
+            ("CLS", []) => Instruction::Cls,
+            ("RET", []) => Instruction::Ret,
+            ("SCD", [Value(n)]) => Instruction::ScrollDown(nibble(n)?),
+            ("SCU", [Value(n)]) => Instruction::ScrollUp(nibble(n)?),
+            ("SCR", []) => Instruction::ScrollRight,
+            ("SCL", []) => Instruction::ScrollLeft,
+            ("EXIT", []) => Instruction::Exit,
+            ("LOW", []) => Instruction::Low,
+            ("HIGH", []) => Instruction::High,
+            ("JP", [Value(a)]) => Instruction::Jp(addr(a)?),
+            ("JP", [V(0), Value(a)]) => Instruction::JpV0(addr(a)?),
+            ("CALL", [Value(a)]) => Instruction::Call(addr(a)?),
+            ("SE", [V(x), V(y)]) => Instruction::SeReg { x: *x, y: *y },
+            ("SE", [V(x), Value(kk)]) => Instruction::SeImm {
+                x: *x,
+                kk: byte(kk)?,
+            },
+            ("SNE", [V(x), V(y)]) => Instruction::SneReg { x: *x, y: *y },
+            ("SNE", [V(x), Value(kk)]) => Instruction::SneImm {
+                x: *x,
+                kk: byte(kk)?,
+            },
+            ("SAVE", [V(x), V(y)]) => Instruction::SaveRange { x: *x, y: *y },
+            ("LOAD", [V(x), V(y)]) => Instruction::LoadRange { x: *x, y: *y },
+            ("LD", [V(x), V(y)]) => Instruction::LdReg { x: *x, y: *y },
+            ("LD", [V(x), Value(kk)]) => Instruction::LdImm {
+                x: *x,
+                kk: byte(kk)?,
+            },
+            ("LD", [I, Value(a)]) => Instruction::LdI(addr(a)?),
+            ("LD", [I, Long(a)]) => {
+                Instruction::LdILong(resolve(symbols, line, a, Radix::Address, 0xffff)? as u16)
+            }
+            ("LD", [V(x), Dt]) => Instruction::LdVxDt(*x),
+            ("LD", [V(x), K]) => Instruction::LdVxK(*x),
+            ("LD", [Dt, V(x)]) => Instruction::LdDtVx(*x),
+            ("LD", [St, V(x)]) => Instruction::LdStVx(*x),
+            ("LD", [F, V(x)]) => Instruction::LdF(*x),
+            ("LD", [Hf, V(x)]) => Instruction::LdHf(*x),
+            ("LD", [B, V(x)]) => Instruction::LdB(*x),
+            ("LD", [IndirectI, V(x)]) => Instruction::LdIVx(*x),
+            ("LD", [V(x), IndirectI]) => Instruction::LdVxI(*x),
+            ("LD", [R, V(x)]) => Instruction::LdRVx(*x),
+            ("LD", [V(x), R]) => Instruction::LdVxR(*x),
+            ("ADD", [V(x), V(y)]) => Instruction::Add { x: *x, y: *y },
+            ("ADD", [V(x), Value(kk)]) => Instruction::AddImm {
+                x: *x,
+                kk: byte(kk)?,
+            },
+            ("ADD", [I, V(x)]) => Instruction::AddI(*x),
+            ("OR", [V(x), V(y)]) => Instruction::Or { x: *x, y: *y },
+            ("AND", [V(x), V(y)]) => Instruction::And { x: *x, y: *y },
+            ("XOR", [V(x), V(y)]) => Instruction::Xor { x: *x, y: *y },
+            ("SUB", [V(x), V(y)]) => Instruction::Sub { x: *x, y: *y },
+            ("SHR", [V(x), V(y)]) => Instruction::Shr { x: *x, y: *y },
+            ("SUBN", [V(x), V(y)]) => Instruction::Subn { x: *x, y: *y },
+            ("SHL", [V(x), V(y)]) => Instruction::Shl { x: *x, y: *y },
+            ("RND", [V(x), Value(kk)]) => Instruction::Rnd {
+                x: *x,
+                kk: byte(kk)?,
+            },
+            ("DRW", [V(x), V(y), Value(n)]) => Instruction::Drw {
+                x: *x,
+                y: *y,
+                n: nibble(n)?,
+            },
+            ("SKP", [V(x)]) => Instruction::Skp(*x),
+            ("SKNP", [V(x)]) => Instruction::Sknp(*x),
+            ("PLANE", [Value(n)]) => Instruction::Plane(nibble(n)?),
+            ("AUDIO", []) => Instruction::Audio,
+            ("PITCH", [V(x)]) => Instruction::Pitch(*x),
+            _ if MNEMONICS.contains(&mnemonic.as_str()) => {
+                return Err(AsmError::InvalidOperands { line })
+            }
+            _ => {
+                return Err(AsmError::UnknownMnemonic {
+                    line,
+                    mnemonic: statement.mnemonic.to_string(),
+                })
+            }
+        };
+        Ok(instruction)
+    }
+}
+
+/// Size in bytes of what the statement assembles to
+fn size(statement: &Statement) -> usize {
+    match statement.mnemonic.to_ascii_lowercase().as_str() {
+        "db" => statement.operands.len(),
+        "dw" => 2 * statement.operands.len(),
+        "ld" if statement
+            .operands
+            .get(1)
+            .is_some_and(|op| long(op).is_some()) =>
+        {
+            4
+        }
+        _ => 2,
+    }
+}
+
+fn operand(text: &str) -> Operand<'_> {
+    match text.to_ascii_uppercase().as_str() {
+        "I" => Operand::I,
+        "[I]" => Operand::IndirectI,
+        "DT" => Operand::Dt,
+        "ST" => Operand::St,
+        "K" => Operand::K,
+        "F" => Operand::F,
+        "HF" => Operand::Hf,
+        "B" => Operand::B,
+        "R" => Operand::R,
+        _ => {
+            if let Some(x) = register(text) {
+                Operand::V(x)
+            } else if let Some(addr) = long(text) {
+                Operand::Long(addr)
+            } else {
+                Operand::Value(text)
+            }
+        }
+    }
+}
+
+/// `v0` to `vf`
+fn register(text: &str) -> Option<u8> {
+    let digit = text.strip_prefix(['v', 'V'])?;
+    if digit.len() != 1 {
+        return None;
+    }
+    u8::from_str_radix(digit, 16).ok()
+}
+
+/// The address in `LONG NNNN`
+fn long(text: &str) -> Option<&str> {
+    let (keyword, addr) = text.split_once(char::is_whitespace)?;
+    keyword.eq_ignore_ascii_case("LONG").then_some(addr.trim())
+}
+
+fn define(
+    symbols: &mut HashMap<String, usize>,
+    name: &str,
+    value: usize,
+    line: usize,
+) -> Result<(), AsmError> {
+    if !is_identifier(name) {
+        return Err(AsmError::UnknownMnemonic {
+            line,
+            mnemonic: name.to_string(),
+        });
+    }
+    if symbols.insert(name.to_string(), value).is_some() {
+        return Err(AsmError::DuplicateSymbol {
+            line,
+            name: name.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '.')
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// The value of a symbol or number, which must be at most `max`. Symbols take
+/// precedence over addresses, for labels that happen to spell a hex number.
+fn resolve(
+    symbols: &HashMap<String, usize>,
+    line: usize,
+    text: &str,
+    radix: Radix,
+    max: usize,
+) -> Result<usize, AsmError> {
+    let value = symbols
+        .get(text)
+        .copied()
+        .or_else(|| parse_number(text, radix));
+    match value {
+        Some(value) if value <= max => Ok(value),
+        None if is_identifier(text) => Err(AsmError::UndefinedSymbol {
+            line,
+            name: text.to_string(),
+        }),
+        _ => Err(AsmError::InvalidValue {
+            line,
+            value: text.to_string(),
+        }),
+    }
+}
+
+fn parse_number(text: &str, radix: Radix) -> Option<usize> {
+    let prefixed = |prefixes: &[&str]| prefixes.iter().find_map(|prefix| text.strip_prefix(prefix));
+    let (digits, radix) = if let Some(digits) = prefixed(&["0x", "0X", "$", "#"]) {
+        (digits, 16)
+    } else if let Some(digits) = prefixed(&["%"]) {
+        (digits, 2)
+    } else if let (Radix::Decimal, Some(digits)) = (radix, prefixed(&["0b", "0B"])) {
+        // Addresses like 0b0 are hex
+        (digits, 2)
+    } else {
+        match radix {
+            Radix::Decimal => (text, 10),
+            Radix::Address => (text, 16),
+        }
+    };
+    // from_str_radix would take a sign
+    if !digits.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    usize::from_str_radix(digits, radix).ok()
+}
@@ -0,0 +1,194 @@
+use crate::constants::{DEFAULT_PITCH, FRAMES_PER_SECOND};
+use std::fs::File;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Sound state for one 60 Hz frame, handed to an [`AudioSink`] by
+/// [`Interpreter::tick_timers`](crate::Interpreter::tick_timers).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioFrame {
+    /// Whether the sound timer is running during this frame
+    pub playing: bool,
+    /// XO-CHIP 1-bit samples, `None` until the program loads a pattern with F002
+    pub pattern: Option<[u8; 16]>,
+    /// XO-CHIP pitch register, the pattern plays at 4000 * 2^((pitch - 64) / 48) Hz
+    pub pitch: u8,
+}
+
+impl AudioFrame {
+    pub fn silent() -> Self {
+        AudioFrame {
+            playing: false,
+            pattern: None,
+            pitch: DEFAULT_PITCH,
+        }
+    }
+}
+
+/// Receives the sound state once per frame. Sinks that need a sample stream can
+/// render each frame with a [`Buzzer`].
+pub trait AudioSink {
+    fn play(&mut self, frame: &AudioFrame);
+}
+
+pub const DEFAULT_SAMPLE_RATE: u32 = 44100;
+pub const DEFAULT_FREQUENCY: f32 = 440.0;
+pub const DEFAULT_VOLUME: f32 = 0.25;
+
+/// Generates the buzzer as signed samples between -1 and 1: a square wave at a fixed
+/// frequency, or the XO-CHIP pattern once the program has loaded one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Buzzer {
+    sample_rate: u32,
+    frequency: f32,
+    volume: f32,
+    // Position in the current wave period or pattern, kept across frames so the tone
+    // doesn't click at frame boundaries
+    phase: f32,
+}
+
+impl Buzzer {
+    pub fn new(sample_rate: u32) -> Self {
+        Buzzer {
+            sample_rate,
+            frequency: DEFAULT_FREQUENCY,
+            volume: DEFAULT_VOLUME,
+            phase: 0.0,
+        }
+    }
+
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Volume from 0 (silent) to 1 (full scale)
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn samples_per_frame(&self) -> usize {
+        (self.sample_rate / FRAMES_PER_SECOND) as usize
+    }
+
+    /// Renders one frame worth of samples
+    pub fn render(&mut self, frame: &AudioFrame) -> Vec<f32> {
+        let mut samples = Vec::with_capacity(self.samples_per_frame());
+        if !frame.playing {
+            self.phase = 0.0;
+            samples.resize(self.samples_per_frame(), 0.0);
+            return samples;
+        }
+
+        for _ in 0..self.samples_per_frame() {
+            let high = match frame.pattern {
+                Some(pattern) => {
+                    let bit = self.phase as usize % 128;
+                    let rate = 4000.0 * 2f32.powf((frame.pitch as f32 - 64.0) / 48.0);
+                    self.phase = (self.phase + rate / self.sample_rate as f32) % 128.0;
+                    pattern[bit / 8] & (0x80 >> (bit % 8)) != 0
+                }
+                None => {
+                    let high = self.phase < 0.5;
+                    self.phase = (self.phase + self.frequency / self.sample_rate as f32) % 1.0;
+                    high
+                }
+            };
+            samples.push(if high { self.volume } else { -self.volume });
+        }
+        samples
+    }
+}
+
+impl Default for Buzzer {
+    fn default() -> Self {
+        Buzzer::new(DEFAULT_SAMPLE_RATE)
+    }
+}
+
+const WAV_HEADER_LENGTH: u32 = 44;
+
+/// Records the buzzer to a 16-bit mono WAV file, so sound can be checked without an
+/// audio device. The header is kept up to date after every frame, which leaves a
+/// valid file behind even if the program is killed.
+pub struct WavSink<W: Write + Seek> {
+    writer: W,
+    buzzer: Buzzer,
+    data_length: u32,
+    error: Option<io::Error>,
+}
+
+impl WavSink<File> {
+    pub fn create<P: AsRef<Path>>(path: P, buzzer: Buzzer) -> io::Result<Self> {
+        WavSink::new(File::create(path)?, buzzer)
+    }
+}
+
+impl<W: Write + Seek> WavSink<W> {
+    pub fn new(mut writer: W, buzzer: Buzzer) -> io::Result<Self> {
+        let sample_rate = buzzer.sample_rate();
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(WAV_HEADER_LENGTH - 8).to_le_bytes())?;
+        writer.write_all(b"WAVEfmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?; // fmt chunk length
+        writer.write_all(&1u16.to_le_bytes())?; // PCM
+        writer.write_all(&1u16.to_le_bytes())?; // mono
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * 2).to_le_bytes())?; // bytes per second
+        writer.write_all(&2u16.to_le_bytes())?; // bytes per sample
+        writer.write_all(&16u16.to_le_bytes())?; // bits per sample
+        writer.write_all(b"data")?;
+        writer.write_all(&0u32.to_le_bytes())?;
+
+        Ok(WavSink {
+            writer,
+            buzzer,
+            data_length: 0,
+            error: None,
+        })
+    }
+
+    /// The first write error, after which the sink stops recording
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_frame(&mut self, frame: &AudioFrame) -> io::Result<()> {
+        let bytes: Vec<u8> = self
+            .buzzer
+            .render(frame)
+            .into_iter()
+            .flat_map(|sample| ((sample * i16::MAX as f32) as i16).to_le_bytes())
+            .collect();
+        self.writer.write_all(&bytes)?;
+        self.data_length += bytes.len() as u32;
+
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer
+            .write_all(&(WAV_HEADER_LENGTH - 8 + self.data_length).to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(40))?;
+        self.writer.write_all(&self.data_length.to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> AudioSink for WavSink<W> {
+    fn play(&mut self, frame: &AudioFrame) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Err(e) = self.write_frame(frame) {
+            self.error = Some(e);
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use crate::disassembler::Disassembler;
+use crate::error::ExecError;
+use crate::interpreter::Interpreter;
+use crate::util::{ExecutionState, InterpreterResult};
+use crate::watch::{Access, WatchHit, Watchpoint};
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+pub const HELP: &str = "commands:
+    break [addr]        set a breakpoint, or list them without an address (b)
+    delete <addr>       remove a breakpoint (d)
+    step [n]            execute n instructions, 1 by default (s)
+    watch [addr] [len] [r|w|rw]
+                        stop when memory is accessed, writes by default, or
+                        list the watchpoints without an address (w)
+    unwatch <addr>      remove the watchpoints at an address
+    continue            resume execution (c)
+    pause               stop execution (p)
+    regs                show the registers (r)
+    stack               show the call stack
+    timers              show the delay and sound timers
+    mem <addr> [len]    dump memory, 64 bytes by default (m)
+    dis [addr] [n]      disassemble n instructions, around PC by default
+    help                show this message (h)";
+
+/// Instructions shown before PC when disassembling around it
+const CONTEXT_BEFORE: usize = 4;
+const DEFAULT_DISASSEMBLY_LENGTH: usize = 10;
+const DEFAULT_DUMP_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Break(Option<u16>),
+    Delete(u16),
+    Step(usize),
+    Watch(Option<Watchpoint>),
+    Unwatch(usize),
+    Continue,
+    Pause,
+    Registers,
+    Stack,
+    Timers,
+    Memory { addr: usize, length: usize },
+    Disassemble { addr: Option<u16>, count: usize },
+    Help,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let name = words.next().ok_or("empty command")?;
+        let args: Vec<&str> = words.collect();
+        let arg = |k: usize| args.get(k).copied();
+
+        let command = match name {
+            "break" | "b" => Command::Break(arg(0).map(parse_addr).transpose()?),
+            "delete" | "d" => {
+                Command::Delete(parse_addr(arg(0).ok_or("delete expects an address")?)?)
+            }
+            "step" | "s" => Command::Step(arg(0).map(parse_count).transpose()?.unwrap_or(1)),
+            "watch" | "w" => Command::Watch(match arg(0) {
+                Some(addr) => Some(Watchpoint::new(
+                    parse_addr(addr)? as usize,
+                    arg(1).map(parse_count).transpose()?.unwrap_or(1),
+                    arg(2)
+                        .map(parse_access)
+                        .transpose()?
+                        .unwrap_or(Access::Write),
+                )),
+                None => None,
+            }),
+            "unwatch" => {
+                Command::Unwatch(parse_addr(arg(0).ok_or("unwatch expects an address")?)? as usize)
+            }
+            "continue" | "c" => Command::Continue,
+            "pause" | "p" => Command::Pause,
+            "regs" | "r" => Command::Registers,
+            "stack" => Command::Stack,
+            "timers" => Command::Timers,
+            "mem" | "m" => Command::Memory {
+                addr: parse_addr(arg(0).ok_or("mem expects an address")?)? as usize,
+                length: arg(1)
+                    .map(parse_count)
+                    .transpose()?
+                    .unwrap_or(DEFAULT_DUMP_LENGTH),
+            },
+            "dis" => Command::Disassemble {
+                addr: arg(0).map(parse_addr).transpose()?,
+                count: arg(1)
+                    .map(parse_count)
+                    .transpose()?
+                    .unwrap_or(DEFAULT_DISASSEMBLY_LENGTH),
+            },
+            "help" | "h" => Command::Help,
+            _ => return Err(format!("unknown command {}, try help", name)),
+        };
+        Ok(command)
+    }
+}
+
+/// What happened when the debugger was asked to run a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugEvent {
+    /// The frame ran to completion
+    Ran(InterpreterResult),
+    /// Execution is paused, nothing ran
+    Paused,
+    /// Execution stopped before the instruction at this address
+    Breakpoint(u16),
+    /// Execution stopped after an instruction accessed watched memory
+    Watchpoint(WatchHit),
+}
+
+/// Breakpoints and stepping on top of an [`Interpreter`], driven by [`Command`]s.
+#[derive(Debug, Clone, Default)]
+pub struct Debugger {
+    breakpoints: BTreeSet<u16>,
+    paused: bool,
+    // Set when resuming from a breakpoint so it doesn't trigger again right away
+    resuming: bool,
+    disassembler: Disassembler,
+}
+
+impl Debugger {
+    /// Starts paused, so breakpoints can be set before the program runs
+    pub fn new() -> Self {
+        Debugger {
+            paused: true,
+            ..Debugger::default()
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = u16> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    /// Returns false if there already was a breakpoint at `addr`
+    pub fn set_breakpoint(&mut self, addr: u16) -> bool {
+        self.breakpoints.insert(addr)
+    }
+
+    /// Returns false if there was no breakpoint at `addr`
+    pub fn remove_breakpoint(&mut self, addr: u16) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes execution, going past a breakpoint at PC
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.resuming = true;
+    }
+
+    /// Pauses and executes `count` instructions, ignoring breakpoints. Stops early
+    /// when a watchpoint fires.
+    pub fn step(
+        &mut self,
+        interpreter: &mut Interpreter,
+        count: usize,
+    ) -> Result<Option<WatchHit>, ExecError> {
+        self.paused = true;
+        for _ in 0..count {
+            interpreter.tick()?;
+            if let Some(&hit) = interpreter.take_watch_hits().first() {
+                return Ok(Some(hit));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Runs a frame like [`Interpreter::run_frame`] unless paused, stopping before
+    /// any instruction on a breakpoint. Timers only tick once the frame completes.
+    pub fn run_frame(
+        &mut self,
+        interpreter: &mut Interpreter,
+        instructions_per_frame: usize,
+    ) -> Result<DebugEvent, ExecError> {
+        if self.paused {
+            return Ok(DebugEvent::Paused);
+        }
+
+        let mut frame_result = InterpreterResult {
+            refresh_display: false,
+            wait_for_keyboard: None,
+        };
+        for _ in 0..instructions_per_frame {
+            let pc = interpreter.pc();
+            if self.breakpoints.contains(&pc)
+                && !std::mem::take(&mut self.resuming)
+                && interpreter.state() == ExecutionState::Running
+            {
+                self.paused = true;
+                return Ok(DebugEvent::Breakpoint(pc));
+            }
+            self.resuming = false;
+
+            let result = interpreter.tick()?;
+            if let Some(&hit) = interpreter.take_watch_hits().first() {
+                self.paused = true;
+                return Ok(DebugEvent::Watchpoint(hit));
+            }
+            frame_result.refresh_display |= result.refresh_display;
+            frame_result.wait_for_keyboard = result.wait_for_keyboard;
+            if result.wait_for_keyboard.is_some()
+                || (result.refresh_display && interpreter.quirks().display_wait)
+            {
+                break;
+            }
+        }
+
+        interpreter.tick_timers();
+        Ok(DebugEvent::Ran(frame_result))
+    }
+
+    /// Runs a command and returns what to show the user
+    pub fn execute(
+        &mut self,
+        command: Command,
+        interpreter: &mut Interpreter,
+    ) -> Result<String, ExecError> {
+        let output = match command {
+            Command::Break(Some(addr)) => {
+                self.set_breakpoint(addr);
+                format!("breakpoint at {:03x}", addr)
+            }
+            Command::Break(None) if self.breakpoints.is_empty() => "no breakpoints".to_string(),
+            Command::Break(None) => self
+                .breakpoints
+                .iter()
+                .map(|addr| format!("{:03x}", addr))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Command::Delete(addr) => {
+                if self.remove_breakpoint(addr) {
+                    format!("deleted breakpoint at {:03x}", addr)
+                } else {
+                    format!("no breakpoint at {:03x}", addr)
+                }
+            }
+            Command::Step(count) => {
+                let hit = self.step(interpreter, count)?;
+                let line = self.disassemble(interpreter, None, 1);
+                match hit {
+                    Some(hit) => format!("watchpoint: {}\n{}", hit, line),
+                    None => line,
+                }
+            }
+            Command::Watch(Some(watchpoint)) => match interpreter.add_watchpoint(watchpoint) {
+                Some(watchpoint) => format!("watching {}", format_watchpoint(&watchpoint)),
+                None => format!("{:03x} is past the end of memory", watchpoint.addr),
+            },
+            Command::Watch(None) if interpreter.watchpoints().is_empty() => {
+                "no watchpoints".to_string()
+            }
+            Command::Watch(None) => interpreter
+                .watchpoints()
+                .iter()
+                .map(format_watchpoint)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Command::Unwatch(addr) => {
+                if interpreter.remove_watchpoint(addr) {
+                    format!("deleted watchpoints at {:03x}", addr)
+                } else {
+                    format!("no watchpoint at {:03x}", addr)
+                }
+            }
+            Command::Continue => {
+                self.resume();
+                "running".to_string()
+            }
+            Command::Pause => {
+                self.pause();
+                self.disassemble(interpreter, None, 1)
+            }
+            Command::Registers => format_registers(interpreter),
+            Command::Stack if interpreter.stack().is_empty() => "stack is empty".to_string(),
+            Command::Stack => interpreter
+                .stack()
+                .frames()
+                .iter()
+                .rev()
+                .map(|addr| format!("{:03x}", addr))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Command::Timers => format!(
+                "dt {:02x}  st {:02x}",
+                interpreter.delay_timer(),
+                interpreter.sound_timer()
+            ),
+            Command::Memory { addr, length } => dump_memory(&interpreter.memory, addr, length),
+            Command::Disassemble { addr, count } => self.disassemble(interpreter, addr, count),
+            Command::Help => HELP.to_string(),
+        };
+        Ok(output)
+    }
+
+    /// Disassembles `count` instructions from `addr`, or around PC without one. The
+    /// line at PC is marked with `>` and breakpoints with `*`.
+    fn disassemble(&self, interpreter: &Interpreter, addr: Option<u16>, count: usize) -> String {
+        let pc = interpreter.pc();
+        // Instructions are 2 bytes long apart from F000 NNNN, which is rare enough
+        // that stepping back 2 bytes at a time is a good guess
+        let start = addr.unwrap_or_else(|| {
+            let before = if count > 1 { CONTEXT_BEFORE } else { 0 };
+            pc.saturating_sub(2 * before as u16)
+        });
+
+        self.disassembler
+            .disassemble_at(&interpreter.memory, start as usize, count)
+            .iter()
+            .map(|line| {
+                let at_pc = if line.addr == pc { '>' } else { ' ' };
+                let breakpoint = if self.breakpoints.contains(&line.addr) {
+                    '*'
+                } else {
+                    ' '
+                };
+                format!("{}{} {}", at_pc, breakpoint, line)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn format_registers(interpreter: &Interpreter) -> String {
+    let registers: Vec<String> = interpreter
+        .registers()
+        .iter()
+        .enumerate()
+        .map(|(x, value)| format!("v{:x} {:02x}", x, value))
+        .collect();
+    format!(
+        "pc {:03x}  i {:03x}  sp {}\n{}\n{}",
+        interpreter.pc(),
+        interpreter.index(),
+        interpreter.stack().len(),
+        registers[..8].join("  "),
+        registers[8..].join("  ")
+    )
+}
+
+fn dump_memory(memory: &[u8], addr: usize, length: usize) -> String {
+    let end = addr.saturating_add(length).min(memory.len());
+    (addr.min(end)..end)
+        .step_by(16)
+        .map(|line| {
+            let bytes: Vec<String> = memory[line..(line + 16).min(end)]
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            format!("{:03x}  {}", line, bytes.join(" "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_watchpoint(watchpoint: &Watchpoint) -> String {
+    format!(
+        "{:03x}..{:03x} ({})",
+        watchpoint.addr,
+        watchpoint.addr + watchpoint.length,
+        watchpoint.access
+    )
+}
+
+fn parse_access(value: &str) -> Result<Access, String> {
+    match value {
+        "r" => Ok(Access::Read),
+        "w" => Ok(Access::Write),
+        "rw" => Ok(Access::ReadWrite),
+        _ => Err(format!("invalid access {}, expected r, w or rw", value)),
+    }
+}
+
+fn parse_addr(value: &str) -> Result<u16, String> {
+    let digits = value.trim_start_matches("0x");
+    u16::from_str_radix(digits, 16).map_err(|_| format!("invalid address {}", value))
+}
+
+fn parse_count(value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid count {}", value))
+}
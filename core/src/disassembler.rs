@@ -0,0 +1,342 @@
+use crate::analysis::{Analysis, ByteKind};
+use crate::constants::PC_DEFAULT_START;
+use crate::instruction::{decode_at, Instruction};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// One disassembled instruction, or a `db` line for bytes that aren't one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisasmLine {
+    pub addr: u16,
+    pub bytes: Vec<u8>,
+    pub mnemonic: String,
+    pub operands: Vec<String>,
+    /// Name of the line when something refers to it, `sub_3B0`
+    pub label: Option<String>,
+    /// Addresses of the `CALL`s to a subroutine
+    pub callers: Vec<u16>,
+}
+
+impl DisasmLine {
+    /// The instruction as it would be written in assembly, `LD v1, 42`
+    pub fn text(&self) -> String {
+        join(&self.mnemonic, &self.operands)
+    }
+}
+
+/// `2a4  6101      LD v1, 1`
+impl fmt::Display for DisasmLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes: String = self
+            .bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        write!(f, "{:03x}  {:8}  {}", self.addr, bytes, self.text())
+    }
+}
+
+/// Bytes in a `db` line, short enough for the line to stay readable
+const DATA_PER_LINE: usize = 8;
+
+/// Column of the address comments in a listing
+const COMMENT_COLUMN: usize = 28;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Disassembler {}
+
+impl Disassembler {
+    /// Disassembles a whole ROM, as loaded at `0x200`. Code is found by following
+    /// the control flow, see [`crate::analysis`], and data comes out as `db` lines.
+    /// Bytes the analysis couldn't reach are decoded two at a time, as long as they
+    /// hold valid instructions.
+    pub fn disassemble(&self, program: &[u8]) -> Vec<DisasmLine> {
+        let analysis = Analysis::new(program);
+        let mut lines = Vec::new();
+        let mut offset = 0;
+        while offset < program.len() {
+            let line = match analysis.kinds[offset] {
+                ByteKind::Code if analysis.instructions.contains(&offset) => {
+                    self.line_in(program, offset, PC_DEFAULT_START)
+                }
+                ByteKind::Data => {
+                    let end = (offset + 1..program.len())
+                        .find(|&end| {
+                            end - offset == DATA_PER_LINE
+                                || analysis.kinds[end] != ByteKind::Data
+                                || analysis.data.contains(&end)
+                        })
+                        .unwrap_or(program.len());
+                    data_line(program, offset, end)
+                }
+                _ => self.unreached_line(program, offset, &analysis),
+            };
+            offset += line.bytes.len();
+            lines.push(line);
+        }
+        add_labels(&mut lines, &analysis);
+        lines
+    }
+
+    /// Disassembles a whole ROM as source for the assembler: labels on lines of
+    /// their own, each subroutine preceded by the list of its callers, and the
+    /// address and bytes of every line in a comment.
+    pub fn listing(&self, program: &[u8]) -> String {
+        let mut listing = String::new();
+        for line in self.disassemble(program) {
+            if let Some(label) = &line.label {
+                listing.push('\n');
+                if !line.callers.is_empty() {
+                    let callers: Vec<String> = line
+                        .callers
+                        .iter()
+                        .map(|addr| format!("{:03x}", addr))
+                        .collect();
+                    listing += &format!("; called from {}\n", callers.join(", "));
+                }
+                listing += &format!("{}:\n", label);
+            }
+            let bytes: String = line
+                .bytes
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            listing += &format!(
+                "    {:width$} ; {:03x}  {}\n",
+                line.text(),
+                line.addr,
+                bytes,
+                width = COMMENT_COLUMN - 5
+            );
+        }
+        listing
+    }
+
+    /// Linear sweep fallback, for bytes the analysis didn't reach
+    fn unreached_line(&self, program: &[u8], offset: usize, analysis: &Analysis) -> DisasmLine {
+        let instruction = decode_at(program, offset);
+        let end = offset + instruction.size();
+        let unreached = analysis
+            .kinds
+            .get(offset..end)
+            .is_some_and(|kinds| kinds.iter().all(|&kind| kind == ByteKind::Unknown));
+        // SYS calls machine code, which is mostly zeroed padding being decoded
+        let valid = !matches!(instruction, Instruction::Unknown(_) | Instruction::Sys(_));
+        if unreached && valid {
+            return self.line_in(program, offset, PC_DEFAULT_START);
+        }
+
+        let end = (offset + 1..(offset + 2).min(program.len()))
+            .find(|&end| analysis.kinds[end] != ByteKind::Unknown)
+            .unwrap_or((offset + 2).min(program.len()));
+        data_line(program, offset, end)
+    }
+
+    /// Disassembles `count` instructions of `memory` starting at `addr`
+    pub fn disassemble_at(&self, memory: &[u8], addr: usize, count: usize) -> Vec<DisasmLine> {
+        let mut lines = Vec::new();
+        let mut addr = addr;
+        while lines.len() < count && addr < memory.len() {
+            let line = self.line_in(memory, addr, 0);
+            addr += line.bytes.len();
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// The instruction at `offset` in `memory`, which is mapped at `base`. A lone
+    /// byte at the end becomes a `db` line.
+    fn line_in(&self, memory: &[u8], offset: usize, base: usize) -> DisasmLine {
+        let addr = (base + offset) as u16;
+        let instruction = decode_at(memory, offset);
+        let end = offset + instruction.size();
+        if end > memory.len() {
+            return data_line(memory, offset, memory.len());
+        }
+
+        let (mnemonic, operands) = self.parts(&instruction);
+        DisasmLine {
+            addr,
+            bytes: memory[offset..end].to_vec(),
+            mnemonic: mnemonic.to_string(),
+            operands,
+            label: None,
+            callers: Vec::new(),
+        }
+    }
+
+    pub fn format(&self, instruction: &Instruction) -> String {
+        let (mnemonic, operands) = self.parts(instruction);
+        join(mnemonic, &operands)
+    }
+
+    /// Mnemonic and operands, in Cowgod's notation
+    fn parts(&self, instruction: &Instruction) -> (&'static str, Vec<String>) {
+        let v = |x: u8| format!("v{:1x}", x);
+        let target = |addr: u16| format!("{:03x}", addr);
+        match *instruction {
+            Instruction::Sys(addr) => ("SYS", vec![target(addr)]),
+            Instruction::Cls => ("CLS", vec![]),
+            Instruction::Ret => ("RET", vec![]),
+            Instruction::ScrollDown(n) => ("SCD", vec![n.to_string()]),
+            Instruction::ScrollUp(n) => ("SCU", vec![n.to_string()]),
+            Instruction::ScrollRight => ("SCR", vec![]),
+            Instruction::ScrollLeft => ("SCL", vec![]),
+            Instruction::Exit => ("EXIT", vec![]),
+            Instruction::Low => ("LOW", vec![]),
+            Instruction::High => ("HIGH", vec![]),
+            Instruction::Jp(addr) => ("JP", vec![target(addr)]),
+            Instruction::Call(addr) => ("CALL", vec![target(addr)]),
+            Instruction::SeImm { x, kk } => ("SE", vec![v(x), kk.to_string()]),
+            Instruction::SneImm { x, kk } => ("SNE", vec![v(x), kk.to_string()]),
+            Instruction::SeReg { x, y } => ("SE", vec![v(x), v(y)]),
+            Instruction::SaveRange { x, y } => ("SAVE", vec![v(x), v(y)]),
+            Instruction::LoadRange { x, y } => ("LOAD", vec![v(x), v(y)]),
+            Instruction::LdImm { x, kk } => ("LD", vec![v(x), kk.to_string()]),
+            Instruction::AddImm { x, kk } => ("ADD", vec![v(x), kk.to_string()]),
+            Instruction::LdReg { x, y } => ("LD", vec![v(x), v(y)]),
+            Instruction::Or { x, y } => ("OR", vec![v(x), v(y)]),
+            Instruction::And { x, y } => ("AND", vec![v(x), v(y)]),
+            Instruction::Xor { x, y } => ("XOR", vec![v(x), v(y)]),
+            Instruction::Add { x, y } => ("ADD", vec![v(x), v(y)]),
+            Instruction::Sub { x, y } => ("SUB", vec![v(x), v(y)]),
+            Instruction::Shr { x, y } => ("SHR", vec![v(x), v(y)]),
+            Instruction::Subn { x, y } => ("SUBN", vec![v(x), v(y)]),
+            Instruction::Shl { x, y } => ("SHL", vec![v(x), v(y)]),
+            Instruction::SneReg { x, y } => ("SNE", vec![v(x), v(y)]),
+            Instruction::LdI(addr) => ("LD", vec!["I".to_string(), target(addr)]),
+            Instruction::JpV0(addr) => ("JP", vec!["v0".to_string(), target(addr)]),
+            Instruction::Rnd { x, kk } => ("RND", vec![v(x), kk.to_string()]),
+            Instruction::Drw { x, y, n } => ("DRW", vec![v(x), v(y), n.to_string()]),
+            Instruction::Skp(x) => ("SKP", vec![v(x)]),
+            Instruction::Sknp(x) => ("SKNP", vec![v(x)]),
+            Instruction::LdILong(addr) => {
+                ("LD", vec!["I".to_string(), format!("LONG {:04x}", addr)])
+            }
+            Instruction::Plane(n) => ("PLANE", vec![n.to_string()]),
+            Instruction::Audio => ("AUDIO", vec![]),
+            Instruction::LdVxDt(x) => ("LD", vec![v(x), "DT".to_string()]),
+            Instruction::LdVxK(x) => ("LD", vec![v(x), "K".to_string()]),
+            Instruction::LdDtVx(x) => ("LD", vec!["DT".to_string(), v(x)]),
+            Instruction::LdStVx(x) => ("LD", vec!["ST".to_string(), v(x)]),
+            Instruction::AddI(x) => ("ADD", vec!["I".to_string(), v(x)]),
+            Instruction::LdF(x) => ("LD", vec!["F".to_string(), v(x)]),
+            Instruction::LdHf(x) => ("LD", vec!["HF".to_string(), v(x)]),
+            Instruction::LdB(x) => ("LD", vec!["B".to_string(), v(x)]),
+            Instruction::Pitch(x) => ("PITCH", vec![v(x)]),
+            Instruction::LdIVx(x) => ("LD", vec!["[I]".to_string(), v(x)]),
+            Instruction::LdVxI(x) => ("LD", vec![v(x), "[I]".to_string()]),
+            Instruction::LdRVx(x) => ("LD", vec!["R".to_string(), v(x)]),
+            Instruction::LdVxR(x) => ("LD", vec![v(x), "R".to_string()]),
+            // Not an instruction, so written as a data word
+            Instruction::Unknown(raw) => ("dw", vec![format!("0x{:04x}", raw)]),
+        }
+    }
+}
+
+/// `db` line for `program[start..end]`, mapped at `0x200`
+fn data_line(program: &[u8], start: usize, end: usize) -> DisasmLine {
+    DisasmLine {
+        addr: (PC_DEFAULT_START + start) as u16,
+        bytes: program[start..end].to_vec(),
+        mnemonic: "db".to_string(),
+        operands: program[start..end]
+            .iter()
+            .map(|byte| format!("0x{:02x}", byte))
+            .collect(),
+        label: None,
+        callers: Vec::new(),
+    }
+}
+
+/// What a label names, in order of precedence when a line is several of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum LabelKind {
+    Data,
+    Sprite,
+    Label,
+    Loop,
+    Sub,
+}
+
+/// Names every jump, call and `LD I` target that starts a line, then writes the
+/// names in place of the addresses
+fn add_labels(lines: &mut [DisasmLine], analysis: &Analysis) {
+    let starts: BTreeSet<u16> = lines.iter().map(|line| line.addr).collect();
+    let mut labels = BTreeMap::new();
+    let mut callers: BTreeMap<u16, Vec<u16>> = BTreeMap::new();
+    for line in lines.iter() {
+        let (target, _) = match target(line) {
+            Some(target) => target,
+            None => continue,
+        };
+        let kind = match decode_at(&line.bytes, 0) {
+            Instruction::Call(_) => {
+                callers.entry(target).or_default().push(line.addr);
+                LabelKind::Sub
+            }
+            Instruction::Jp(_) | Instruction::JpV0(_) if target <= line.addr => LabelKind::Loop,
+            Instruction::Jp(_) | Instruction::JpV0(_) => LabelKind::Label,
+            _ => {
+                let offset = target as usize - PC_DEFAULT_START;
+                if analysis.sprites.contains(&offset) {
+                    LabelKind::Sprite
+                } else {
+                    LabelKind::Data
+                }
+            }
+        };
+        // A target in the middle of a line keeps its address
+        if starts.contains(&target) {
+            let label = labels.entry(target).or_insert(kind);
+            *label = (*label).max(kind);
+        }
+    }
+
+    let names: BTreeMap<u16, String> = labels
+        .iter()
+        .map(|(&addr, kind)| {
+            let prefix = match kind {
+                LabelKind::Data => "data",
+                LabelKind::Sprite => "sprite",
+                LabelKind::Label => "label",
+                LabelKind::Loop => "loop",
+                LabelKind::Sub => "sub",
+            };
+            (addr, format!("{}_{:03X}", prefix, addr))
+        })
+        .collect();
+    for line in lines.iter_mut() {
+        if let Some((target, operand)) = target(line) {
+            if let Some(name) = names.get(&target) {
+                line.operands[operand] = match line.operands[operand].strip_prefix("LONG ") {
+                    Some(_) => format!("LONG {}", name),
+                    None => name.clone(),
+                };
+            }
+        }
+        line.label = names.get(&line.addr).cloned();
+        line.callers = callers.remove(&line.addr).unwrap_or_default();
+    }
+}
+
+/// The address a jump, call or `LD I` line refers to, and the operand holding it
+fn target(line: &DisasmLine) -> Option<(u16, usize)> {
+    if line.mnemonic == "db" {
+        return None;
+    }
+    let (addr, operand) = match decode_at(&line.bytes, 0) {
+        Instruction::Jp(addr) | Instruction::Call(addr) => (addr, 0),
+        Instruction::JpV0(addr) | Instruction::LdI(addr) | Instruction::LdILong(addr) => (addr, 1),
+        _ => return None,
+    };
+    (addr as usize >= PC_DEFAULT_START).then_some((addr, operand))
+}
+
+fn join(mnemonic: &str, operands: &[String]) -> String {
+    if operands.is_empty() {
+        mnemonic.to_string()
+    } else {
+        format!("{} {}", mnemonic, operands.join(", "))
+    }
+}
@@ -0,0 +1,197 @@
+use crate::constants::{MEMORY_SIZE, XO_CHIP_MEMORY_SIZE};
+use std::error::Error;
+use std::fmt;
+
+/// Reasons the interpreter can fail to execute an instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecError {
+    /// The op code at `pc` is not one the interpreter knows how to run.
+    UnknownOpcode { pc: u16, opcode: u16 },
+    /// A `CALL` was made with the call stack already full.
+    StackOverflow,
+    /// A `RET` was made with an empty call stack.
+    StackUnderflow,
+    /// An instruction tried to read or write outside of RAM.
+    MemoryOutOfBounds { addr: usize },
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecError::UnknownOpcode { pc, opcode } => {
+                write!(f, "unknown op code {:04x} at {:03x}", opcode, pc)
+            }
+            ExecError::StackOverflow => write!(f, "stack overflow"),
+            ExecError::StackUnderflow => write!(f, "stack underflow"),
+            ExecError::MemoryOutOfBounds { addr } => {
+                write!(f, "memory access out of bounds at {:x}", addr)
+            }
+        }
+    }
+}
+
+impl Error for ExecError {}
+
+/// Reasons a save state can't be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// The data doesn't start with the save state magic bytes.
+    NotASaveState,
+    /// The state was written by a newer or older, incompatible format.
+    UnsupportedVersion(u16),
+    /// The state was saved while running another ROM.
+    RomMismatch { expected: u64, found: u64 },
+    /// The data ends early or holds values the interpreter can't be in.
+    Corrupted,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::NotASaveState => write!(f, "not a save state"),
+            StateError::UnsupportedVersion(version) => {
+                write!(f, "unsupported save state version {}", version)
+            }
+            StateError::RomMismatch { expected, found } => write!(
+                f,
+                "save state is for another ROM (hash {:016x}, running {:016x})",
+                found, expected
+            ),
+            StateError::Corrupted => write!(f, "save state is corrupted"),
+        }
+    }
+}
+
+impl Error for StateError {}
+
+/// Reasons a ROM can't be loaded into a new interpreter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    /// The ROM doesn't fit in memory from `0x200` on.
+    RomTooLarge { size: usize, max: usize },
+    /// Memory has to be 4 KiB, or 64 KiB for XO-CHIP.
+    UnsupportedMemorySize(usize),
+    /// The stack depth doesn't fit in a save state.
+    StackTooDeep { depth: usize, max: usize },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::RomTooLarge { size, max } => {
+                write!(f, "ROM is {} bytes, only {} fit in memory", size, max)
+            }
+            LoadError::UnsupportedMemorySize(size) => write!(
+                f,
+                "unsupported memory size {}, expected {} or {}",
+                size, MEMORY_SIZE, XO_CHIP_MEMORY_SIZE
+            ),
+            LoadError::StackTooDeep { depth, max } => {
+                write!(f, "stack depth {} is over the maximum of {}", depth, max)
+            }
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// Reasons a movie can't be read or played back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MovieError {
+    /// The movie file is malformed at the given line, counting from 1.
+    Invalid { line: usize },
+    /// The movie was recorded while running another ROM.
+    RomMismatch { expected: u64, found: u64 },
+    /// The machine state after `frame` differs from the one recorded in the movie.
+    Desync {
+        frame: usize,
+        expected: u64,
+        found: u64,
+    },
+    /// `frame` is past the last of the movie's `length` frames.
+    EndOfMovie { frame: usize, length: usize },
+    /// The interpreter failed while playing the movie.
+    Exec(ExecError),
+    /// The ROM can't be run with the movie's quirks.
+    Load(LoadError),
+}
+
+impl fmt::Display for MovieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MovieError::Invalid { line } => write!(f, "invalid movie at line {}", line),
+            MovieError::RomMismatch { expected, found } => write!(
+                f,
+                "movie is for another ROM (hash {:016x}, running {:016x})",
+                found, expected
+            ),
+            MovieError::Desync {
+                frame,
+                expected,
+                found,
+            } => write!(
+                f,
+                "desync at frame {}: state hash {:016x}, recorded {:016x}",
+                frame, found, expected
+            ),
+            MovieError::EndOfMovie { frame, length } => write!(
+                f,
+                "frame {} is past the end of the movie, which has {} frames",
+                frame, length
+            ),
+            MovieError::Exec(e) => write!(f, "{}", e),
+            MovieError::Load(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for MovieError {}
+
+impl From<ExecError> for MovieError {
+    fn from(e: ExecError) -> Self {
+        MovieError::Exec(e)
+    }
+}
+
+impl From<LoadError> for MovieError {
+    fn from(e: LoadError) -> Self {
+        MovieError::Load(e)
+    }
+}
+
+/// Reasons a source file can't be assembled. Lines count from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmError {
+    /// The line starts with something that isn't an instruction or directive.
+    UnknownMnemonic { line: usize, mnemonic: String },
+    /// The operands don't match any form of the instruction.
+    InvalidOperands { line: usize },
+    /// A label or constant is used but never defined.
+    UndefinedSymbol { line: usize, name: String },
+    /// A label or constant is defined a second time.
+    DuplicateSymbol { line: usize, name: String },
+    /// A number can't be read, or doesn't fit in its operand.
+    InvalidValue { line: usize, value: String },
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsmError::UnknownMnemonic { line, mnemonic } => {
+                write!(f, "line {}: unknown mnemonic {}", line, mnemonic)
+            }
+            AsmError::InvalidOperands { line } => write!(f, "line {}: invalid operands", line),
+            AsmError::UndefinedSymbol { line, name } => {
+                write!(f, "line {}: undefined symbol {}", line, name)
+            }
+            AsmError::DuplicateSymbol { line, name } => {
+                write!(f, "line {}: {} is already defined", line, name)
+            }
+            AsmError::InvalidValue { line, value } => {
+                write!(f, "line {}: invalid value {}", line, value)
+            }
+        }
+    }
+}
+
+impl Error for AsmError {}
@@ -0,0 +1,229 @@
+/// How a sprite overlapped the display, see [`Framebuffer::xor_sprite_rows`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Collision {
+    /// Sprite rows where a lit pixel was turned off
+    pub rows_collided: usize,
+    /// Sprite rows that were clipped off the bottom of the screen
+    pub rows_clipped: usize,
+}
+
+/// The first drawing plane, the only one outside of XO-CHIP
+pub const PLANE_1: u8 = 0b01;
+
+/// The second XO-CHIP drawing plane
+pub const PLANE_2: u8 = 0b10;
+
+/// Display with up to two bitplanes, stored one byte per pixel in row-major order.
+///
+/// Each pixel holds a bit per plane, so its value doubles as a color index from 0 to 3.
+/// Methods that take a `planes` mask only touch the planes whose bit is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    /// Rebuilds a framebuffer from the color indices returned by [`Framebuffer::pixels`]
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), width * height);
+        Framebuffer {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Whether the pixel is lit on any plane
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.pixel(x, y) != 0
+    }
+
+    /// Color index of the pixel, bit 0 is the first plane and bit 1 the second
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Turns the pixel on or off on the first plane
+    pub fn set(&mut self, x: usize, y: usize, on: bool) {
+        let pixel = &mut self.pixels[y * self.width + x];
+        if on {
+            *pixel |= PLANE_1;
+        } else {
+            *pixel &= !PLANE_1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pixels.fill(0);
+    }
+
+    pub fn clear_planes(&mut self, planes: u8) {
+        for pixel in self.pixels.iter_mut() {
+            *pixel &= !planes;
+        }
+    }
+
+    /// Color indices of every pixel in row-major order
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Color indices of each row, top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        self.pixels.chunks(self.width)
+    }
+
+    /// XORs an 8 pixel wide sprite onto the first plane, one byte per row with the most
+    /// significant bit leftmost. Returns whether any lit pixel was turned off.
+    pub fn xor_sprite(&mut self, x: usize, y: usize, sprite: &[u8], clip: bool) -> bool {
+        let rows: Vec<u16> = sprite.iter().map(|&line| (line as u16) << 8).collect();
+        self.xor_sprite_rows(x, y, &rows, 8, PLANE_1, clip)
+            .rows_collided
+            > 0
+    }
+
+    /// XORs a sprite up to 16 pixels wide onto the given planes, one `u16` per row with
+    /// the most significant bit leftmost. The starting position always wraps around the
+    /// screen, the part of the sprite that overflows it is either clipped or wrapped.
+    pub fn xor_sprite_rows(
+        &mut self,
+        x: usize,
+        y: usize,
+        rows: &[u16],
+        width: usize,
+        planes: u8,
+        clip: bool,
+    ) -> Collision {
+        let mut collision = Collision::default();
+        let x = x % self.width;
+        let y = y % self.height;
+
+        for (j, sprite_line) in rows.iter().enumerate() {
+            let mut row = y + j;
+            if row >= self.height {
+                if clip {
+                    collision.rows_clipped = rows.len() - j;
+                    break;
+                }
+                row %= self.height;
+            }
+
+            let mut collided = false;
+            for k in 0..width {
+                let mut column = x + k;
+                if column >= self.width {
+                    if clip {
+                        break;
+                    }
+                    column %= self.width;
+                }
+
+                if sprite_line & (0x8000 >> k) != 0 {
+                    let pixel = &mut self.pixels[row * self.width + column];
+                    collided |= *pixel & planes != 0;
+                    *pixel ^= planes;
+                }
+            }
+            if collided {
+                collision.rows_collided += 1;
+            }
+        }
+
+        collision
+    }
+
+    pub fn scroll_up(&mut self, n: usize, planes: u8) {
+        let n = n.min(self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let below = if y + n < self.height {
+                    self.pixel(x, y + n)
+                } else {
+                    0
+                };
+                self.replace_planes(x, y, below, planes);
+            }
+        }
+    }
+
+    pub fn scroll_down(&mut self, n: usize, planes: u8) {
+        let n = n.min(self.height);
+        for y in (0..self.height).rev() {
+            for x in 0..self.width {
+                let above = if y >= n { self.pixel(x, y - n) } else { 0 };
+                self.replace_planes(x, y, above, planes);
+            }
+        }
+    }
+
+    pub fn scroll_right(&mut self, n: usize, planes: u8) {
+        let n = n.min(self.width);
+        for y in 0..self.height {
+            for x in (0..self.width).rev() {
+                let left = if x >= n { self.pixel(x - n, y) } else { 0 };
+                self.replace_planes(x, y, left, planes);
+            }
+        }
+    }
+
+    pub fn scroll_left(&mut self, n: usize, planes: u8) {
+        let n = n.min(self.width);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let right = if x + n < self.width {
+                    self.pixel(x + n, y)
+                } else {
+                    0
+                };
+                self.replace_planes(x, y, right, planes);
+            }
+        }
+    }
+
+    /// Packs the first plane 8 pixels per byte, most significant bit leftmost, the way
+    /// the VIP kept the display in RAM.
+    pub fn to_packed_bytes(&self) -> Vec<u8> {
+        self.pixels
+            .chunks(8)
+            .map(|pixels| {
+                pixels
+                    .iter()
+                    .enumerate()
+                    .fold(0, |byte, (k, &pixel)| byte | (pixel & PLANE_1) << (7 - k))
+            })
+            .collect()
+    }
+
+    /// Plain PBM image of the display, with a pixel set when it is lit on any plane
+    pub fn to_pbm(&self) -> String {
+        let mut pbm = format!("P1\n{} {}\n", self.width, self.height);
+        for row in self.rows() {
+            pbm.extend(row.iter().map(|&pixel| if pixel != 0 { '1' } else { '0' }));
+            pbm.push('\n');
+        }
+        pbm
+    }
+
+    fn replace_planes(&mut self, x: usize, y: usize, value: u8, planes: u8) {
+        let pixel = &mut self.pixels[y * self.width + x];
+        *pixel = (*pixel & !planes) | (value & planes);
+    }
+}
@@ -0,0 +1,522 @@
+//! A GDB remote serial protocol stub, so GDB and scripts speaking RSP can drive an
+//! [`Interpreter`]: registers, memory, software breakpoints, single-step and
+//! continue.
+//!
+//! The stub only deals with bytes and leaves the transport to the frontend. What
+//! the client sends goes to [`GdbStub::receive`], and replies pile up until
+//! [`GdbStub::take_output`].
+//!
+//! Registers are numbered V0 to VF, then I, PC, SP, DT and ST, as described by the
+//! `target.xml` served over `qXfer`. I and PC are sent as 2 big-endian bytes like
+//! instructions in memory, the others as a single byte. SP is the call stack depth
+//! and can't be changed.
+
+use crate::debugger::{DebugEvent, Debugger};
+use crate::error::ExecError;
+use crate::interpreter::Interpreter;
+use crate::watch::{Access, WatchHit, Watchpoint};
+use std::fmt::Write;
+
+/// Largest packet the client may send, advertised in `qSupported`
+const PACKET_SIZE: usize = 0x1000;
+/// Sent outside of packets to stop a running target
+const INTERRUPT: u8 = 0x03;
+
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+const SIGSEGV: u8 = 11;
+
+/// Width in bytes of each register, in the order they're numbered
+const REGISTER_SIZES: [usize; 21] = [
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 1, 1, 1,
+];
+const SP: usize = 18;
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.chip8.core">
+    <reg name="v0" bitsize="8" type="uint8" regnum="0"/>
+    <reg name="v1" bitsize="8" type="uint8"/>
+    <reg name="v2" bitsize="8" type="uint8"/>
+    <reg name="v3" bitsize="8" type="uint8"/>
+    <reg name="v4" bitsize="8" type="uint8"/>
+    <reg name="v5" bitsize="8" type="uint8"/>
+    <reg name="v6" bitsize="8" type="uint8"/>
+    <reg name="v7" bitsize="8" type="uint8"/>
+    <reg name="v8" bitsize="8" type="uint8"/>
+    <reg name="v9" bitsize="8" type="uint8"/>
+    <reg name="va" bitsize="8" type="uint8"/>
+    <reg name="vb" bitsize="8" type="uint8"/>
+    <reg name="vc" bitsize="8" type="uint8"/>
+    <reg name="vd" bitsize="8" type="uint8"/>
+    <reg name="ve" bitsize="8" type="uint8"/>
+    <reg name="vf" bitsize="8" type="uint8"/>
+    <reg name="i" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+    <reg name="sp" bitsize="8" type="uint8"/>
+    <reg name="dt" bitsize="8" type="uint8"/>
+    <reg name="st" bitsize="8" type="uint8"/>
+  </feature>
+</target>
+"#;
+
+#[derive(Debug, Clone, Default)]
+enum Reading {
+    #[default]
+    Idle,
+    Packet(Vec<u8>),
+    Checksum(Vec<u8>, Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+pub struct GdbStub {
+    debugger: Debugger,
+    reading: Reading,
+    output: Vec<u8>,
+    no_ack: bool,
+    // Reply sent for the last stop, for `?`
+    stop: String,
+    attached: bool,
+}
+
+impl Default for GdbStub {
+    fn default() -> Self {
+        GdbStub::new()
+    }
+}
+
+impl GdbStub {
+    /// Starts with the target stopped, which is what GDB expects when it connects
+    pub fn new() -> Self {
+        GdbStub {
+            debugger: Debugger::new(),
+            reading: Reading::Idle,
+            output: Vec::new(),
+            no_ack: false,
+            stop: stop_reply(SIGTRAP),
+            attached: true,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.debugger.is_paused()
+    }
+
+    /// False once the client detached or killed the target
+    pub fn is_attached(&self) -> bool {
+        self.attached
+    }
+
+    /// Bytes to send to the client
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    /// Handles bytes from the client, which may hold partial or several packets
+    pub fn receive(&mut self, bytes: &[u8], interpreter: &mut Interpreter) {
+        for &byte in bytes {
+            self.reading = match std::mem::take(&mut self.reading) {
+                Reading::Idle if byte == b'$' => Reading::Packet(Vec::new()),
+                Reading::Idle => {
+                    if byte == INTERRUPT {
+                        self.interrupt();
+                    }
+                    // Anything else is an ack, replies are never resent
+                    Reading::Idle
+                }
+                Reading::Packet(data) if byte == b'#' => Reading::Checksum(data, Vec::new()),
+                Reading::Packet(mut data) => {
+                    data.push(byte);
+                    Reading::Packet(data)
+                }
+                Reading::Checksum(data, mut digits) => {
+                    digits.push(byte);
+                    if digits.len() < 2 {
+                        Reading::Checksum(data, digits)
+                    } else {
+                        let expected = std::str::from_utf8(&digits)
+                            .ok()
+                            .and_then(|digits| u8::from_str_radix(digits, 16).ok());
+                        let valid = expected == Some(checksum(&data));
+                        if !self.no_ack {
+                            self.output.push(if valid { b'+' } else { b'-' });
+                        }
+                        if valid {
+                            let packet = String::from_utf8_lossy(&unescape(&data)).into_owned();
+                            self.handle(&packet, interpreter);
+                        }
+                        Reading::Idle
+                    }
+                }
+            };
+        }
+    }
+
+    /// Runs a frame like [`Debugger::run_frame`], reporting breakpoints and crashes
+    /// to the client
+    pub fn run_frame(
+        &mut self,
+        interpreter: &mut Interpreter,
+        instructions_per_frame: usize,
+    ) -> Result<DebugEvent, ExecError> {
+        let event = self.debugger.run_frame(interpreter, instructions_per_frame);
+        match &event {
+            Ok(DebugEvent::Breakpoint(_)) => self.stop(stop_reply(SIGTRAP)),
+            Ok(DebugEvent::Watchpoint(hit)) => self.stop(watch_reply(hit)),
+            Err(e) => {
+                self.debugger.pause();
+                self.stop(stop_reply(signal(e)));
+            }
+            Ok(_) => (),
+        }
+        event
+    }
+
+    fn interrupt(&mut self) {
+        if !self.debugger.is_paused() {
+            self.debugger.pause();
+            self.stop(stop_reply(SIGINT));
+        }
+    }
+
+    fn stop(&mut self, reply: String) {
+        self.send(&reply);
+        self.stop = reply;
+    }
+
+    fn send(&mut self, payload: &str) {
+        let mut data = Vec::with_capacity(payload.len());
+        for &byte in payload.as_bytes() {
+            if matches!(byte, b'$' | b'#' | b'}' | b'*') {
+                data.extend([b'}', byte ^ 0x20]);
+            } else {
+                data.push(byte);
+            }
+        }
+        self.output.push(b'$');
+        self.output.extend(&data);
+        self.output
+            .extend(format!("#{:02x}", checksum(&data)).as_bytes());
+    }
+
+    fn handle(&mut self, packet: &str, interpreter: &mut Interpreter) {
+        let command = packet.get(..1).unwrap_or("");
+        let args = packet.get(1..).unwrap_or("");
+        let reply = match command {
+            "?" => Some(self.stop.clone()),
+            "g" => Some(to_hex(&read_registers(interpreter))),
+            "G" => Some(status(
+                from_hex(args).and_then(|bytes| write_registers(interpreter, &bytes)),
+            )),
+            "p" => Some(
+                usize::from_str_radix(args, 16)
+                    .ok()
+                    .and_then(|n| read_register(interpreter, n))
+                    .map_or_else(|| "E01".to_string(), |bytes| to_hex(&bytes)),
+            ),
+            "P" => Some(status(args.split_once('=').and_then(|(n, value)| {
+                let n = usize::from_str_radix(n, 16).ok()?;
+                write_register(interpreter, n, &from_hex(value)?)
+            }))),
+            "m" => Some(
+                parse_range(args)
+                    .and_then(|(addr, length)| {
+                        interpreter.memory.get(addr..addr.checked_add(length)?)
+                    })
+                    .map_or_else(|| "E01".to_string(), to_hex),
+            ),
+            "M" => Some(status(args.split_once(':').and_then(|(range, data)| {
+                let (addr, length) = parse_range(range)?;
+                let data = from_hex(data).filter(|data| data.len() == length)?;
+                interpreter
+                    .memory
+                    .get_mut(addr..addr.checked_add(length)?)?
+                    .copy_from_slice(&data);
+                Some(())
+            }))),
+            "Z" | "z" => Some(self.breakpoint(command == "Z", args, interpreter)),
+            "c" => self.resume(args, interpreter),
+            "s" => self.step(args, interpreter),
+            "v" => self.v_packet(packet, interpreter),
+            "q" | "Q" => Some(self.query(packet)),
+            "H" => Some("OK".to_string()),
+            "D" => {
+                self.detach();
+                Some("OK".to_string())
+            }
+            "k" => {
+                self.detach();
+                None
+            }
+            _ => Some(String::new()),
+        };
+        if let Some(reply) = reply {
+            self.send(&reply);
+        }
+        if packet == "QStartNoAckMode" {
+            self.no_ack = true;
+        }
+    }
+
+    /// `Z`/`z` packets: software and hardware breakpoints are the same thing here,
+    /// types 2 to 4 are write, read and access watchpoints
+    fn breakpoint(&mut self, insert: bool, args: &str, interpreter: &mut Interpreter) -> String {
+        let mut fields = args.split(',');
+        let access = match fields.next() {
+            Some("0" | "1") => None,
+            Some("2") => Some(Access::Write),
+            Some("3") => Some(Access::Read),
+            Some("4") => Some(Access::ReadWrite),
+            _ => return String::new(),
+        };
+        let addr = fields
+            .next()
+            .and_then(|addr| usize::from_str_radix(addr, 16).ok());
+        // Conditions may follow the length, they aren't supported
+        let length = fields
+            .next()
+            .and_then(|length| usize::from_str_radix(length.split(';').next()?, 16).ok());
+        let (addr, length) = match addr.zip(length) {
+            Some(range) => range,
+            None => return "E01".to_string(),
+        };
+        // Breakpoints are kept as 16-bit addresses, which must not wrap around
+        if access.is_none() && addr >= interpreter.memory.len() {
+            return "E01".to_string();
+        }
+
+        match (access, insert) {
+            (None, true) => {
+                self.debugger.set_breakpoint(addr as u16);
+            }
+            (None, false) => {
+                self.debugger.remove_breakpoint(addr as u16);
+            }
+            (Some(access), true) => {
+                if interpreter
+                    .add_watchpoint(Watchpoint::new(addr, length, access))
+                    .is_none()
+                {
+                    return "E01".to_string();
+                }
+            }
+            (Some(_), false) => {
+                interpreter.remove_watchpoint(addr);
+            }
+        }
+        "OK".to_string()
+    }
+
+    /// `c [addr]`, which only replies once the target stops
+    fn resume(&mut self, args: &str, interpreter: &mut Interpreter) -> Option<String> {
+        if !args.is_empty() {
+            match u16::from_str_radix(args, 16) {
+                Ok(addr) => interpreter.set_pc(addr),
+                Err(_) => return Some("E01".to_string()),
+            }
+        }
+        self.debugger.resume();
+        None
+    }
+
+    /// `s [addr]`, replying with the stop once the instruction ran
+    fn step(&mut self, args: &str, interpreter: &mut Interpreter) -> Option<String> {
+        if !args.is_empty() {
+            match u16::from_str_radix(args, 16) {
+                Ok(addr) => interpreter.set_pc(addr),
+                Err(_) => return Some("E01".to_string()),
+            }
+        }
+        self.stop = match self.debugger.step(interpreter, 1) {
+            Ok(Some(hit)) => watch_reply(&hit),
+            Ok(None) => stop_reply(SIGTRAP),
+            Err(e) => stop_reply(signal(&e)),
+        };
+        Some(self.stop.clone())
+    }
+
+    fn v_packet(&mut self, packet: &str, interpreter: &mut Interpreter) -> Option<String> {
+        if packet == "vCont?" {
+            return Some("vCont;c;C;s;S".to_string());
+        }
+        let actions = match packet.strip_prefix("vCont;") {
+            Some(actions) => actions,
+            None => return Some(String::new()),
+        };
+        // There is a single thread, so the first action is the one that applies
+        match actions.split([';', ':']).next().unwrap_or("").get(..1) {
+            Some("c" | "C") => self.resume("", interpreter),
+            Some("s" | "S") => self.step("", interpreter),
+            _ => Some("E01".to_string()),
+        }
+    }
+
+    fn query(&self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            return format!(
+                "PacketSize={:x};qXfer:features:read+;QStartNoAckMode+",
+                PACKET_SIZE
+            );
+        }
+        if let Some(range) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            return match parse_range(range) {
+                Some((offset, length)) => {
+                    let rest = TARGET_XML.get(offset..).unwrap_or("");
+                    if rest.len() <= length {
+                        format!("l{}", rest)
+                    } else {
+                        format!("m{}", &rest[..length])
+                    }
+                }
+                None => "E01".to_string(),
+            };
+        }
+        match packet {
+            "QStartNoAckMode" => "OK",
+            "qAttached" => "1",
+            "qC" => "QC1",
+            "qfThreadInfo" => "m1",
+            "qsThreadInfo" => "l",
+            _ => "",
+        }
+        .to_string()
+    }
+
+    /// Forgets the breakpoints and lets the program run on its own, for when the
+    /// client is gone
+    pub fn detach(&mut self) {
+        for addr in self.debugger.breakpoints().collect::<Vec<_>>() {
+            self.debugger.remove_breakpoint(addr);
+        }
+        self.debugger.resume();
+        self.attached = false;
+    }
+}
+
+fn stop_reply(signal: u8) -> String {
+    format!("S{:02x}", signal)
+}
+
+/// Tells the client which kind of watchpoint fired and on which address
+fn watch_reply(hit: &WatchHit) -> String {
+    let kind = match hit.access {
+        Access::Read => "rwatch",
+        _ => "watch",
+    };
+    format!("T{:02x}{}:{:x};", SIGTRAP, kind, hit.addr)
+}
+
+fn signal(error: &ExecError) -> u8 {
+    match error {
+        ExecError::UnknownOpcode { .. } => SIGILL,
+        _ => SIGSEGV,
+    }
+}
+
+fn read_registers(interpreter: &Interpreter) -> Vec<u8> {
+    let mut bytes = interpreter.registers().to_vec();
+    bytes.extend(interpreter.index().to_be_bytes());
+    bytes.extend(interpreter.pc().to_be_bytes());
+    bytes.push(interpreter.stack().len() as u8);
+    bytes.push(interpreter.delay_timer());
+    bytes.push(interpreter.sound_timer());
+    bytes
+}
+
+fn read_register(interpreter: &Interpreter, n: usize) -> Option<Vec<u8>> {
+    let offset: usize = REGISTER_SIZES.get(..n)?.iter().sum();
+    let size = *REGISTER_SIZES.get(n)?;
+    Some(read_registers(interpreter)[offset..offset + size].to_vec())
+}
+
+fn write_register(interpreter: &mut Interpreter, n: usize, value: &[u8]) -> Option<()> {
+    if REGISTER_SIZES.get(n) != Some(&value.len()) {
+        return None;
+    }
+    let word = || u16::from_be_bytes([value[0], value[1]]);
+    match n {
+        0..=15 => interpreter.set_register(n as u8, value[0]),
+        16 => interpreter.set_index(word()),
+        17 => interpreter.set_pc(word()),
+        SP if value[0] as usize == interpreter.stack().len() => (),
+        SP => return None,
+        19 => interpreter.set_delay_timer(value[0]),
+        _ => interpreter.set_sound_timer(value[0]),
+    }
+    Some(())
+}
+
+fn write_registers(interpreter: &mut Interpreter, bytes: &[u8]) -> Option<()> {
+    if bytes.len() != REGISTER_SIZES.iter().sum::<usize>() {
+        return None;
+    }
+    let mut offset = 0;
+    let values: Vec<&[u8]> = REGISTER_SIZES
+        .iter()
+        .map(|&size| {
+            offset += size;
+            &bytes[offset - size..offset]
+        })
+        .collect();
+    // Check SP first so nothing changes when it's wrong
+    if values[SP][0] as usize != interpreter.stack().len() {
+        return None;
+    }
+    for (n, value) in values.into_iter().enumerate() {
+        write_register(interpreter, n, value)?;
+    }
+    Some(())
+}
+
+/// `addr,length` in hex
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    let (addr, length) = range.split_once(',')?;
+    Some((
+        usize::from_str_radix(addr, 16).ok()?,
+        usize::from_str_radix(length, 16).ok()?,
+    ))
+}
+
+fn status(result: Option<()>) -> String {
+    match result {
+        Some(()) => "OK",
+        None => "E01",
+    }
+    .to_string()
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, &byte| sum.wrapping_add(byte))
+}
+
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(data.len());
+    let mut escaped = false;
+    for &byte in data {
+        match byte {
+            _ if escaped => {
+                bytes.push(byte ^ 0x20);
+                escaped = false;
+            }
+            b'}' => escaped = true,
+            _ => bytes.push(byte),
+        }
+    }
+    bytes
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|k| u8::from_str_radix(hex.get(k..k + 2)?, 16).ok())
+        .collect()
+}
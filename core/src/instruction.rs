@@ -0,0 +1,329 @@
+use crate::quirks::Quirks;
+use crate::util::OpCode;
+
+/// A decoded CHIP-8 instruction.
+///
+/// `x` and `y` are register indices, `kk` an 8-bit immediate, `n` a 4-bit
+/// immediate and addresses are 12 bits wide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    /// 0NNN: call a native machine code routine
+    Sys(u16),
+    /// 00E0
+    Cls,
+    /// 00EE
+    Ret,
+    /// 00CN: scroll the display down N pixels (SUPER-CHIP)
+    ScrollDown(u8),
+    /// 00DN: scroll the display up N pixels (XO-CHIP)
+    ScrollUp(u8),
+    /// 00FB: scroll the display right 4 pixels (SUPER-CHIP)
+    ScrollRight,
+    /// 00FC: scroll the display left 4 pixels (SUPER-CHIP)
+    ScrollLeft,
+    /// 00FD: exit the interpreter (SUPER-CHIP)
+    Exit,
+    /// 00FE: switch to 64x32 (SUPER-CHIP)
+    Low,
+    /// 00FF: switch to 128x64 (SUPER-CHIP)
+    High,
+    /// 1NNN
+    Jp(u16),
+    /// 2NNN
+    Call(u16),
+    /// 3XKK: skip next if VX == KK
+    SeImm { x: u8, kk: u8 },
+    /// 4XKK: skip next if VX != KK
+    SneImm { x: u8, kk: u8 },
+    /// 5XY0: skip next if VX == VY
+    SeReg { x: u8, y: u8 },
+    /// 5XY2: store VX..=VY at I (XO-CHIP)
+    SaveRange { x: u8, y: u8 },
+    /// 5XY3: load VX..=VY from I (XO-CHIP)
+    LoadRange { x: u8, y: u8 },
+    /// 6XKK
+    LdImm { x: u8, kk: u8 },
+    /// 7XKK
+    AddImm { x: u8, kk: u8 },
+    /// 8XY0
+    LdReg { x: u8, y: u8 },
+    /// 8XY1
+    Or { x: u8, y: u8 },
+    /// 8XY2
+    And { x: u8, y: u8 },
+    /// 8XY3
+    Xor { x: u8, y: u8 },
+    /// 8XY4
+    Add { x: u8, y: u8 },
+    /// 8XY5
+    Sub { x: u8, y: u8 },
+    /// 8XY6
+    Shr { x: u8, y: u8 },
+    /// 8XY7
+    Subn { x: u8, y: u8 },
+    /// 8XYE
+    Shl { x: u8, y: u8 },
+    /// 9XY0: skip next if VX != VY
+    SneReg { x: u8, y: u8 },
+    /// ANNN
+    LdI(u16),
+    /// BNNN
+    JpV0(u16),
+    /// CXKK
+    Rnd { x: u8, kk: u8 },
+    /// DXYN, DXY0 draws a 16x16 sprite on SUPER-CHIP
+    Drw { x: u8, y: u8, n: u8 },
+    /// EX9E
+    Skp(u8),
+    /// EXA1
+    Sknp(u8),
+    /// F000 NNNN: load a 16-bit address into I (XO-CHIP)
+    LdILong(u16),
+    /// FN01: select the drawing planes (XO-CHIP)
+    Plane(u8),
+    /// F002: load the 16 byte audio pattern at I (XO-CHIP)
+    Audio,
+    /// FX07
+    LdVxDt(u8),
+    /// FX0A
+    LdVxK(u8),
+    /// FX15
+    LdDtVx(u8),
+    /// FX18
+    LdStVx(u8),
+    /// FX1E
+    AddI(u8),
+    /// FX29
+    LdF(u8),
+    /// FX30: point I at the large font digit in VX (SUPER-CHIP)
+    LdHf(u8),
+    /// FX33
+    LdB(u8),
+    /// FX3A: set the audio pitch to VX (XO-CHIP)
+    Pitch(u8),
+    /// FX55: store V0..=VX at I
+    LdIVx(u8),
+    /// FX65: load V0..=VX from I
+    LdVxI(u8),
+    /// FX75: store V0..=VX in the RPL user flags (SUPER-CHIP)
+    LdRVx(u8),
+    /// FX85: load V0..=VX from the RPL user flags (SUPER-CHIP)
+    LdVxR(u8),
+    /// Any op code that doesn't match the above
+    Unknown(u16),
+}
+
+impl Instruction {
+    /// Size of the encoded instruction in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            Instruction::LdILong(_) => 4,
+            _ => 2,
+        }
+    }
+
+    /// Whether the quirks enable this instruction, which is always the case outside of
+    /// the SUPER-CHIP and XO-CHIP extensions
+    pub fn is_enabled(&self, quirks: &Quirks) -> bool {
+        match self {
+            Instruction::ScrollDown(_)
+            | Instruction::ScrollRight
+            | Instruction::ScrollLeft
+            | Instruction::Exit
+            | Instruction::Low
+            | Instruction::High
+            | Instruction::LdHf(_)
+            | Instruction::LdRVx(_)
+            | Instruction::LdVxR(_) => quirks.schip_opcodes,
+            Instruction::ScrollUp(_)
+            | Instruction::SaveRange { .. }
+            | Instruction::LoadRange { .. }
+            | Instruction::LdILong(_)
+            | Instruction::Plane(_)
+            | Instruction::Audio
+            | Instruction::Pitch(_) => quirks.xochip_opcodes,
+            _ => true,
+        }
+    }
+}
+
+/// Decodes the instruction starting at `addr`, including the second word of
+/// `F000 NNNN`. Bytes past the end of `memory` read as 0.
+pub fn decode_at(memory: &[u8], addr: usize) -> Instruction {
+    let byte = |offset: usize| memory.get(addr + offset).copied().unwrap_or(0);
+    match decode(&OpCode {
+        first: byte(0),
+        second: byte(1),
+    }) {
+        Instruction::LdILong(_) => Instruction::LdILong((byte(2) as u16) << 8 | byte(3) as u16),
+        instruction => instruction,
+    }
+}
+
+/// Decodes a single op code like [`decode`], except that instructions the quirks
+/// don't enable decode as they did on the VIP: `Sys` in the `0NNN` range and
+/// `Unknown` elsewhere.
+pub fn decode_for(op_code: &OpCode, quirks: &Quirks) -> Instruction {
+    match decode(op_code) {
+        instruction if instruction.is_enabled(quirks) => instruction,
+        _ if op_code.first >> 4 == 0 => Instruction::Sys(op_code.to_u16() & 0xfff),
+        _ => Instruction::Unknown(op_code.to_u16()),
+    }
+}
+
+/// Decodes a single op code. The address of `F000 NNNN` is in the following
+/// word, so it is left as 0 here, see [`decode_at`].
+pub fn decode(op_code: &OpCode) -> Instruction {
+    let raw = op_code.to_u16();
+    let nnn = raw & 0xfff;
+    let x = op_code.first & 0xF;
+    let y = op_code.second >> 4 & 0xF;
+    let n = op_code.second & 0xF;
+    let kk = op_code.second;
+
+    match op_code.first >> 4 & 0xF {
+        0x0 => match raw {
+            0x00e0 => Instruction::Cls,
+            0x00ee => Instruction::Ret,
+            0x00c0..=0x00cf => Instruction::ScrollDown(n),
+            0x00d0..=0x00df => Instruction::ScrollUp(n),
+            0x00fb => Instruction::ScrollRight,
+            0x00fc => Instruction::ScrollLeft,
+            0x00fd => Instruction::Exit,
+            0x00fe => Instruction::Low,
+            0x00ff => Instruction::High,
+            _ => Instruction::Sys(nnn),
+        },
+        0x1 => Instruction::Jp(nnn),
+        0x2 => Instruction::Call(nnn),
+        0x3 => Instruction::SeImm { x, kk },
+        0x4 => Instruction::SneImm { x, kk },
+        0x5 => match n {
+            0x0 => Instruction::SeReg { x, y },
+            0x2 => Instruction::SaveRange { x, y },
+            0x3 => Instruction::LoadRange { x, y },
+            _ => Instruction::Unknown(raw),
+        },
+        0x6 => Instruction::LdImm { x, kk },
+        0x7 => Instruction::AddImm { x, kk },
+        0x8 => match n {
+            0x0 => Instruction::LdReg { x, y },
+            0x1 => Instruction::Or { x, y },
+            0x2 => Instruction::And { x, y },
+            0x3 => Instruction::Xor { x, y },
+            0x4 => Instruction::Add { x, y },
+            0x5 => Instruction::Sub { x, y },
+            0x6 => Instruction::Shr { x, y },
+            0x7 => Instruction::Subn { x, y },
+            0xe => Instruction::Shl { x, y },
+            _ => Instruction::Unknown(raw),
+        },
+        0x9 if n == 0 => Instruction::SneReg { x, y },
+        0xa => Instruction::LdI(nnn),
+        0xb => Instruction::JpV0(nnn),
+        0xc => Instruction::Rnd { x, kk },
+        0xd => Instruction::Drw { x, y, n },
+        0xe => match kk {
+            0x9e => Instruction::Skp(x),
+            0xa1 => Instruction::Sknp(x),
+            _ => Instruction::Unknown(raw),
+        },
+        0xf => match kk {
+            0x00 if x == 0 => Instruction::LdILong(0),
+            0x01 => Instruction::Plane(x),
+            0x02 if x == 0 => Instruction::Audio,
+            0x07 => Instruction::LdVxDt(x),
+            0x0a => Instruction::LdVxK(x),
+            0x15 => Instruction::LdDtVx(x),
+            0x18 => Instruction::LdStVx(x),
+            0x1e => Instruction::AddI(x),
+            0x29 => Instruction::LdF(x),
+            0x30 => Instruction::LdHf(x),
+            0x33 => Instruction::LdB(x),
+            0x3a => Instruction::Pitch(x),
+            0x55 => Instruction::LdIVx(x),
+            0x65 => Instruction::LdVxI(x),
+            0x75 => Instruction::LdRVx(x),
+            0x85 => Instruction::LdVxR(x),
+            _ => Instruction::Unknown(raw),
+        },
+        _ => Instruction::Unknown(raw),
+    }
+}
+
+/// Encodes the first word of an instruction. `F000 NNNN` is followed by its
+/// address, see [`encode_bytes`].
+pub fn encode(instruction: &Instruction) -> OpCode {
+    let xy = |prefix: u16, x: u8, y: u8, n: u16| {
+        prefix << 12 | (x as u16 & 0xF) << 8 | (y as u16 & 0xF) << 4 | n
+    };
+    let xkk = |prefix: u16, x: u8, kk: u8| prefix << 12 | (x as u16 & 0xF) << 8 | kk as u16;
+    let nnn = |prefix: u16, addr: u16| prefix << 12 | (addr & 0xfff);
+
+    let raw = match *instruction {
+        Instruction::Sys(addr) => nnn(0x0, addr),
+        Instruction::Cls => 0x00e0,
+        Instruction::Ret => 0x00ee,
+        Instruction::ScrollDown(n) => 0x00c0 | (n as u16 & 0xF),
+        Instruction::ScrollUp(n) => 0x00d0 | (n as u16 & 0xF),
+        Instruction::ScrollRight => 0x00fb,
+        Instruction::ScrollLeft => 0x00fc,
+        Instruction::Exit => 0x00fd,
+        Instruction::Low => 0x00fe,
+        Instruction::High => 0x00ff,
+        Instruction::Jp(addr) => nnn(0x1, addr),
+        Instruction::Call(addr) => nnn(0x2, addr),
+        Instruction::SeImm { x, kk } => xkk(0x3, x, kk),
+        Instruction::SneImm { x, kk } => xkk(0x4, x, kk),
+        Instruction::SeReg { x, y } => xy(0x5, x, y, 0x0),
+        Instruction::SaveRange { x, y } => xy(0x5, x, y, 0x2),
+        Instruction::LoadRange { x, y } => xy(0x5, x, y, 0x3),
+        Instruction::LdImm { x, kk } => xkk(0x6, x, kk),
+        Instruction::AddImm { x, kk } => xkk(0x7, x, kk),
+        Instruction::LdReg { x, y } => xy(0x8, x, y, 0x0),
+        Instruction::Or { x, y } => xy(0x8, x, y, 0x1),
+        Instruction::And { x, y } => xy(0x8, x, y, 0x2),
+        Instruction::Xor { x, y } => xy(0x8, x, y, 0x3),
+        Instruction::Add { x, y } => xy(0x8, x, y, 0x4),
+        Instruction::Sub { x, y } => xy(0x8, x, y, 0x5),
+        Instruction::Shr { x, y } => xy(0x8, x, y, 0x6),
+        Instruction::Subn { x, y } => xy(0x8, x, y, 0x7),
+        Instruction::Shl { x, y } => xy(0x8, x, y, 0xe),
+        Instruction::SneReg { x, y } => xy(0x9, x, y, 0x0),
+        Instruction::LdI(addr) => nnn(0xa, addr),
+        Instruction::JpV0(addr) => nnn(0xb, addr),
+        Instruction::Rnd { x, kk } => xkk(0xc, x, kk),
+        Instruction::Drw { x, y, n } => xy(0xd, x, y, n as u16 & 0xF),
+        Instruction::Skp(x) => xkk(0xe, x, 0x9e),
+        Instruction::Sknp(x) => xkk(0xe, x, 0xa1),
+        Instruction::LdILong(_) => 0xf000,
+        Instruction::Plane(n) => xkk(0xf, n, 0x01),
+        Instruction::Audio => 0xf002,
+        Instruction::LdVxDt(x) => xkk(0xf, x, 0x07),
+        Instruction::LdVxK(x) => xkk(0xf, x, 0x0a),
+        Instruction::LdDtVx(x) => xkk(0xf, x, 0x15),
+        Instruction::LdStVx(x) => xkk(0xf, x, 0x18),
+        Instruction::AddI(x) => xkk(0xf, x, 0x1e),
+        Instruction::LdF(x) => xkk(0xf, x, 0x29),
+        Instruction::LdHf(x) => xkk(0xf, x, 0x30),
+        Instruction::LdB(x) => xkk(0xf, x, 0x33),
+        Instruction::Pitch(x) => xkk(0xf, x, 0x3a),
+        Instruction::LdIVx(x) => xkk(0xf, x, 0x55),
+        Instruction::LdVxI(x) => xkk(0xf, x, 0x65),
+        Instruction::LdRVx(x) => xkk(0xf, x, 0x75),
+        Instruction::LdVxR(x) => xkk(0xf, x, 0x85),
+        Instruction::Unknown(raw) => raw,
+    };
+
+    OpCode::from_u16(raw)
+}
+
+/// Encodes the whole instruction, [`Instruction::size`] bytes long.
+pub fn encode_bytes(instruction: &Instruction) -> Vec<u8> {
+    let op_code = encode(instruction);
+    let mut bytes = vec![op_code.first, op_code.second];
+    if let Instruction::LdILong(addr) = instruction {
+        bytes.extend_from_slice(&addr.to_be_bytes());
+    }
+    bytes
+}
@@ -0,0 +1,881 @@
+use crate::audio::{AudioFrame, AudioSink};
+use crate::constants::{
+    BIG_FONT, BIG_FONT_START, DEFAULT_PITCH, DISPLAY_HEIGHT, DISPLAY_MEM_START, DISPLAY_WIDTH,
+    FONT, FONT_START, HIRES_DISPLAY_HEIGHT, HIRES_DISPLAY_WIDTH, PC_DEFAULT_START, STACK_START,
+    VIP_STACK_SLOTS,
+};
+use crate::error::{ExecError, LoadError, StateError};
+use crate::framebuffer::{Framebuffer, PLANE_1, PLANE_2};
+use crate::instruction::{decode_at, decode_for, Instruction};
+use crate::quirks::Quirks;
+use crate::rng::Rng;
+use crate::stack::CallStack;
+use crate::state::{self, StateReader, StateWriter};
+use crate::util::get_bit_at;
+use crate::util::{ExecutionState, InterpreterResult, OpCode};
+use crate::watch::{Access, WatchHit, Watchpoint};
+
+pub struct Interpreter {
+    v: [u8; 16], // general purpose registers
+    i: u16,      // I register, 12-bit wide outside of XO-CHIP
+    dt: u8,      // timer register
+    st: u8,      // sound time register
+    stack: CallStack,
+    framebuffer: Framebuffer,
+    planes: u8,              // XO-CHIP planes selected for drawing
+    rpl: [u8; 16],           // SUPER-CHIP user flags
+    audio_pattern: [u8; 16], // XO-CHIP 1-bit audio samples
+    audio_pattern_loaded: bool,
+    pitch: u8,           // XO-CHIP audio pitch register
+    pc: u16,             // program counter
+    pub memory: Vec<u8>, // RAM
+    keyboard: [bool; 16],
+    rng: Rng,
+    rom_hash: u64,
+    quirks: Quirks,
+    state: ExecutionState,
+    audio_sink: Option<Box<dyn AudioSink>>,
+    watchpoints: Vec<Watchpoint>,
+    watch_hits: Vec<WatchHit>,
+}
+
+impl Interpreter {
+    /// Loads `program` at `0x200`, failing if the quirks don't pass [`Quirks::check`]
+    /// or the program doesn't fit in the memory they give it.
+    pub fn new(program: Vec<u8>, quirks: Quirks) -> Result<Self, LoadError> {
+        quirks.check()?;
+        let max = quirks.memory_size.saturating_sub(PC_DEFAULT_START);
+        if program.len() > max {
+            return Err(LoadError::RomTooLarge {
+                size: program.len(),
+                max,
+            });
+        }
+        let mut memory = vec![0; quirks.memory_size];
+
+        Self::initialize_program(&mut memory, &program);
+        Self::initialize_font(&mut memory);
+
+        Ok(Interpreter {
+            v: [0; 16],
+            i: 0,
+            dt: 0,
+            st: 0,
+            stack: CallStack::new(quirks.stack_depth),
+            framebuffer: Framebuffer::new(DISPLAY_WIDTH, DISPLAY_HEIGHT),
+            planes: PLANE_1,
+            rpl: [0; 16],
+            audio_pattern: [0; 16],
+            audio_pattern_loaded: false,
+            pitch: DEFAULT_PITCH,
+            pc: PC_DEFAULT_START as u16,
+            memory,
+            rom_hash: state::rom_hash(&program),
+            keyboard: [false; 16],
+            rng: Rng::new(0),
+            quirks,
+            state: ExecutionState::Running,
+            audio_sink: None,
+            watchpoints: Vec::new(),
+            watch_hits: Vec::new(),
+        })
+    }
+
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
+    }
+
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.v
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.framebuffer
+    }
+
+    /// The I register
+    pub fn index(&self) -> u16 {
+        self.i
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.dt
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.st
+    }
+
+    /// Sets register `Vx`, for debuggers. Only the low nibble of `x` counts, like in
+    /// op codes.
+    pub fn set_register(&mut self, x: u8, value: u8) {
+        self.v[(x & 0xF) as usize] = value;
+    }
+
+    pub fn set_index(&mut self, value: u16) {
+        self.i = value;
+    }
+
+    pub fn set_pc(&mut self, pc: u16) {
+        self.pc = pc;
+    }
+
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.dt = value;
+    }
+
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.st = value;
+    }
+
+    pub fn audio_pattern(&self) -> &[u8; 16] {
+        &self.audio_pattern
+    }
+
+    pub fn pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    /// What the buzzer plays during the current frame
+    pub fn audio_frame(&self) -> AudioFrame {
+        AudioFrame {
+            playing: self.st > 0,
+            pattern: self.audio_pattern_loaded.then_some(self.audio_pattern),
+            pitch: self.pitch,
+        }
+    }
+
+    /// Sets the sink that receives an [`AudioFrame`] on every [`Interpreter::tick_timers`]
+    pub fn set_audio_sink(&mut self, sink: Box<dyn AudioSink>) {
+        self.audio_sink = Some(sink);
+    }
+
+    /// Reseeds the generator behind `CXNN`. Interpreters start with a seed of 0, so
+    /// runs are reproducible unless the frontend picks another one.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    pub fn stack(&self) -> &CallStack {
+        &self.stack
+    }
+
+    pub fn state(&self) -> ExecutionState {
+        self.state
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// Records accesses to the watched range, see [`Interpreter::take_watch_hits`].
+    /// Ranges running past the end of memory are cut short, and those starting past it
+    /// are refused. Returns the watchpoint as it was added.
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) -> Option<Watchpoint> {
+        let room = self.memory.len().checked_sub(watchpoint.addr)?;
+        if room == 0 {
+            return None;
+        }
+        let watchpoint = Watchpoint {
+            length: watchpoint.length.min(room),
+            ..watchpoint
+        };
+        self.watchpoints.push(watchpoint);
+        Some(watchpoint)
+    }
+
+    /// Removes the watchpoints starting at `addr`, returning false if there were none
+    pub fn remove_watchpoint(&mut self, addr: usize) -> bool {
+        let count = self.watchpoints.len();
+        self.watchpoints
+            .retain(|watchpoint| watchpoint.addr != addr);
+        self.watchpoints.len() != count
+    }
+
+    /// Accesses that hit a watchpoint since the last call, oldest first
+    pub fn take_watch_hits(&mut self) -> Vec<WatchHit> {
+        std::mem::take(&mut self.watch_hits)
+    }
+
+    /// Hash of the ROM the interpreter was created with, see [`state::rom_hash`]
+    pub fn rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+
+    /// Snapshots the whole machine in the format described in [`crate::state`]
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::default();
+        writer.bytes(state::MAGIC);
+        writer.u16(state::VERSION);
+        writer.u64(self.rom_hash);
+        writer
+            .quirks(&self.quirks)
+            .expect("quirks are checked when they are set");
+
+        writer.bytes(&self.v);
+        writer.u16(self.i);
+        writer.u8(self.dt);
+        writer.u8(self.st);
+        writer.u16(self.pc);
+        writer.u16(self.stack.len() as u16);
+        for &addr in self.stack.frames() {
+            writer.u16(addr);
+        }
+        writer.u8(self.planes);
+        writer.bytes(&self.rpl);
+        writer.bool(self.audio_pattern_loaded);
+        writer.bytes(&self.audio_pattern);
+        writer.u8(self.pitch);
+        writer.u16(self.keys());
+        writer.u64(self.rng.state());
+        match self.state {
+            ExecutionState::Running => writer.bytes(&[0, 0, 0]),
+            ExecutionState::WaitingForKey { register, key } => {
+                writer.bytes(&[1, register, key.map_or(0xff, |key| key)])
+            }
+            ExecutionState::Exited => writer.bytes(&[2, 0, 0]),
+        }
+        writer.u16(self.framebuffer.width() as u16);
+        writer.u16(self.framebuffer.height() as u16);
+        writer.bytes(self.framebuffer.pixels());
+        writer.bytes(&self.memory);
+        writer.finish()
+    }
+
+    /// Restores a snapshot from [`Interpreter::save_state`], including the quirks it
+    /// was saved with. States saved while running another ROM are refused, and the
+    /// interpreter is left untouched when loading fails.
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<(), StateError> {
+        let mut reader = StateReader::new(bytes);
+        if reader.bytes(state::MAGIC.len()) != Ok(state::MAGIC) {
+            return Err(StateError::NotASaveState);
+        }
+        let version = reader.u16()?;
+        if version != state::VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+        let rom_hash = reader.u64()?;
+        if rom_hash != self.rom_hash {
+            return Err(StateError::RomMismatch {
+                expected: self.rom_hash,
+                found: rom_hash,
+            });
+        }
+        let quirks = reader.quirks()?;
+
+        let v = reader.array()?;
+        let i = reader.u16()?;
+        let dt = reader.u8()?;
+        let st = reader.u8()?;
+        let pc = reader.u16()?;
+        let mut stack = CallStack::new(quirks.stack_depth);
+        for _ in 0..reader.u16()? {
+            stack
+                .push(reader.u16()?)
+                .map_err(|_| StateError::Corrupted)?;
+        }
+        let planes = reader.u8()?;
+        let rpl = reader.array()?;
+        let audio_pattern_loaded = reader.bool()?;
+        let audio_pattern = reader.array()?;
+        let pitch = reader.u8()?;
+        let keys = reader.u16()?;
+        let rng = Rng::new(reader.u64()?);
+        let execution_state = match reader.array()? {
+            [0, 0, 0] => ExecutionState::Running,
+            [1, register, key] if register < 16 && (key < 16 || key == 0xff) => {
+                ExecutionState::WaitingForKey {
+                    register,
+                    key: (key != 0xff).then_some(key),
+                }
+            }
+            [2, 0, 0] => ExecutionState::Exited,
+            _ => return Err(StateError::Corrupted),
+        };
+        let width = reader.u16()? as usize;
+        let height = reader.u16()? as usize;
+        if ![
+            (DISPLAY_WIDTH, DISPLAY_HEIGHT),
+            (HIRES_DISPLAY_WIDTH, HIRES_DISPLAY_HEIGHT),
+        ]
+        .contains(&(width, height))
+            || planes > (PLANE_1 | PLANE_2)
+        {
+            return Err(StateError::Corrupted);
+        }
+        let pixels = reader.bytes(width * height)?.to_vec();
+        let memory = reader.bytes(quirks.memory_size)?.to_vec();
+        reader.finish()?;
+
+        self.quirks = quirks;
+        self.v = v;
+        self.i = i;
+        self.dt = dt;
+        self.st = st;
+        self.pc = pc;
+        self.stack = stack;
+        self.planes = planes;
+        self.rpl = rpl;
+        self.audio_pattern_loaded = audio_pattern_loaded;
+        self.audio_pattern = audio_pattern;
+        self.pitch = pitch;
+        for (k, pressed) in self.keyboard.iter_mut().enumerate() {
+            *pressed = keys & (1 << k) != 0;
+        }
+        self.rng = rng;
+        self.state = execution_state;
+        self.framebuffer = Framebuffer::from_pixels(width, height, pixels);
+        self.memory = memory;
+        Ok(())
+    }
+
+    /// Runs one 60 Hz frame: up to `instructions_per_frame` instructions followed by a
+    /// single timer decrement. The frame ends early if the display wait quirk is on and
+    /// a sprite was drawn, or if the program is waiting for a key.
+    pub fn run_frame(
+        &mut self,
+        instructions_per_frame: usize,
+    ) -> Result<InterpreterResult, ExecError> {
+        let mut frame_result = InterpreterResult {
+            refresh_display: false,
+            wait_for_keyboard: None,
+        };
+
+        for _ in 0..instructions_per_frame {
+            let result = self.tick()?;
+            frame_result.refresh_display |= result.refresh_display;
+            frame_result.wait_for_keyboard = result.wait_for_keyboard;
+
+            if result.wait_for_keyboard.is_some()
+                || (result.refresh_display && self.quirks.display_wait)
+            {
+                break;
+            }
+        }
+
+        self.tick_timers();
+        Ok(frame_result)
+    }
+
+    /// Executes a single instruction. Timers are left alone, see [`Interpreter::tick_timers`].
+    pub fn tick(&mut self) -> Result<InterpreterResult, ExecError> {
+        match self.state {
+            ExecutionState::WaitingForKey { register, .. } => {
+                return Ok(InterpreterResult {
+                    refresh_display: false,
+                    wait_for_keyboard: Some(register as usize),
+                });
+            }
+            ExecutionState::Exited => {
+                return Ok(InterpreterResult {
+                    refresh_display: false,
+                    wait_for_keyboard: None,
+                });
+            }
+            ExecutionState::Running => (),
+        }
+
+        let op_code = OpCode {
+            first: self.read_memory(self.pc as usize)?,
+            second: self.read_memory(self.pc as usize + 1)?,
+        };
+        let instruction = match decode_for(&op_code, &self.quirks) {
+            Instruction::LdILong(_) => {
+                let left = self.read_memory(self.pc as usize + 2)?;
+                let right = self.read_memory(self.pc as usize + 3)?;
+                Instruction::LdILong((left as u16) << 8 | right as u16)
+            }
+            instruction => instruction,
+        };
+        self.execute(instruction, &op_code)
+    }
+
+    /// Decrements the delay and sound timers, to be called at 60 Hz. The audio sink
+    /// hears the frame that just ran before the sound timer goes down.
+    pub fn tick_timers(&mut self) {
+        let audio_frame = self.audio_frame();
+        if let Some(sink) = self.audio_sink.as_mut() {
+            sink.play(&audio_frame);
+        }
+
+        if self.dt > 0 {
+            self.dt -= 1
+        }
+        if self.st > 0 {
+            self.st -= 1
+        }
+    }
+
+    /// Keypad state as a bitmask, bit `k` set while key `k` is held
+    pub fn keys(&self) -> u16 {
+        self.keyboard
+            .iter()
+            .enumerate()
+            .fold(0, |keys, (k, &pressed)| keys | (pressed as u16) << k)
+    }
+
+    /// Presses and releases keys so the keypad matches the bitmask, see
+    /// [`Interpreter::keys`]
+    pub fn set_keys(&mut self, keys: u16) {
+        for key in 0..16 {
+            let pressed = keys & (1 << key) != 0;
+            if self.keyboard[key as usize] != pressed {
+                self.press_key(key, pressed);
+            }
+        }
+    }
+
+    /// Hash of the whole machine, see [`Interpreter::save_state`]
+    pub fn state_hash(&self) -> u64 {
+        state::hash(&self.save_state())
+    }
+
+    pub fn press_key(&mut self, key: u8, pressed: bool) {
+        self.keyboard[key as usize] = pressed;
+
+        // Like the VIP, FX0A only completes once the key is released
+        match self.state {
+            ExecutionState::WaitingForKey {
+                register,
+                key: None,
+            } if pressed => {
+                self.state = ExecutionState::WaitingForKey {
+                    register,
+                    key: Some(key),
+                };
+            }
+            ExecutionState::WaitingForKey {
+                register,
+                key: Some(held),
+            } if !pressed && held == key => {
+                self.v[register as usize] = key;
+                self.state = ExecutionState::Running;
+            }
+            _ => (),
+        }
+    }
+
+    /// Every read the program makes goes through here so watchpoints see it
+    fn read_memory(&mut self, addr: usize) -> Result<u8, ExecError> {
+        let value = self
+            .memory
+            .get(addr)
+            .copied()
+            .ok_or(ExecError::MemoryOutOfBounds { addr })?;
+        self.watch(addr, Access::Read, value, value);
+        Ok(value)
+    }
+
+    /// Every write the program makes goes through here so watchpoints see it
+    fn write_memory(&mut self, addr: usize, value: u8) -> Result<(), ExecError> {
+        let byte = self
+            .memory
+            .get_mut(addr)
+            .ok_or(ExecError::MemoryOutOfBounds { addr })?;
+        let old = std::mem::replace(byte, value);
+        self.watch(addr, Access::Write, old, value);
+        Ok(())
+    }
+
+    fn watch(&mut self, addr: usize, access: Access, old: u8, new: u8) {
+        let watched = self
+            .watchpoints
+            .iter()
+            .any(|watchpoint| watchpoint.contains(addr) && watchpoint.access.matches(access));
+        if watched {
+            self.watch_hits.push(WatchHit {
+                pc: self.pc,
+                addr,
+                access,
+                old,
+                new,
+            });
+        }
+    }
+
+    fn unknown_op(&self, op_code: &OpCode) -> ExecError {
+        ExecError::UnknownOpcode {
+            pc: self.pc,
+            opcode: op_code.to_u16(),
+        }
+    }
+
+    fn initialize_program(memory: &mut [u8], program: &[u8]) {
+        memory[PC_DEFAULT_START..PC_DEFAULT_START + program.len()].copy_from_slice(program);
+    }
+
+    fn initialize_font(memory: &mut [u8]) {
+        for (i, val) in FONT.iter().enumerate() {
+            memory[FONT_START + i] = *val;
+        }
+        for (i, val) in BIG_FONT.iter().enumerate() {
+            memory[BIG_FONT_START + i] = *val;
+        }
+    }
+
+    fn push_stack(&mut self, addr: u16) -> Result<(), ExecError> {
+        let depth = if self.quirks.vip_memory_layout {
+            // Deeper calls would overwrite the display
+            self.stack.depth().min(VIP_STACK_SLOTS)
+        } else {
+            self.stack.depth()
+        };
+        if self.stack.len() >= depth {
+            return Err(ExecError::StackOverflow);
+        }
+        if self.quirks.vip_memory_layout {
+            let slot = STACK_START + 2 * self.stack.len();
+            self.write_memory(slot, (addr >> 8) as u8)?;
+            self.write_memory(slot + 1, (addr & 0x00FF) as u8)?;
+        }
+        self.stack.push(addr)
+    }
+
+    fn pop_stack(&mut self) -> Result<u16, ExecError> {
+        let addr = self.stack.pop()?;
+        if self.quirks.vip_memory_layout {
+            // RAM is authoritative so ROMs that patch their return address keep working
+            let slot = STACK_START + 2 * self.stack.len();
+            let left = self.read_memory(slot)?;
+            let right = self.read_memory(slot + 1)?;
+            return Ok((left as u16) << 8 | right as u16);
+        }
+        Ok(addr)
+    }
+
+    fn shift_source(&self, x: u8, y: u8) -> u8 {
+        if self.quirks.shift_uses_vy {
+            self.v[y as usize]
+        } else {
+            self.v[x as usize]
+        }
+    }
+
+    fn reset_vf(&mut self) {
+        if self.quirks.vf_reset {
+            self.v[0xF] = 0;
+        }
+    }
+
+    /// Address of the instruction after the one at `addr`, which is 4 bytes long for
+    /// `F000 NNNN` on XO-CHIP
+    fn skip_from(&self, addr: u16) -> u16 {
+        let instruction = decode_at(&self.memory, addr as usize);
+        let size = if instruction.is_enabled(&self.quirks) {
+            instruction.size()
+        } else {
+            2
+        };
+        addr.wrapping_add(size as u16)
+    }
+
+    fn clear_screen(&mut self) {
+        self.framebuffer.clear_planes(self.planes);
+        self.mirror_display();
+    }
+
+    fn execute(
+        &mut self,
+        instruction: Instruction,
+        op_code: &OpCode,
+    ) -> Result<InterpreterResult, ExecError> {
+        let mut refresh_display = false;
+        let mut wait_for_keyboard = None;
+        let mut next_pc = self.pc.wrapping_add(instruction.size() as u16);
+
+        match instruction {
+            Instruction::Cls => {
+                self.clear_screen();
+                refresh_display = true;
+            }
+            Instruction::ScrollDown(n) => {
+                self.framebuffer.scroll_down(n as usize, self.planes);
+                self.mirror_display();
+                refresh_display = true;
+            }
+            Instruction::ScrollUp(n) => {
+                self.framebuffer.scroll_up(n as usize, self.planes);
+                self.mirror_display();
+                refresh_display = true;
+            }
+            Instruction::ScrollRight => {
+                self.framebuffer.scroll_right(4, self.planes);
+                self.mirror_display();
+                refresh_display = true;
+            }
+            Instruction::ScrollLeft => {
+                self.framebuffer.scroll_left(4, self.planes);
+                self.mirror_display();
+                refresh_display = true;
+            }
+            Instruction::Exit => self.state = ExecutionState::Exited,
+            Instruction::Low => {
+                self.framebuffer = Framebuffer::new(DISPLAY_WIDTH, DISPLAY_HEIGHT);
+                self.mirror_display();
+                refresh_display = true;
+            }
+            Instruction::High => {
+                self.framebuffer = Framebuffer::new(HIRES_DISPLAY_WIDTH, HIRES_DISPLAY_HEIGHT);
+                refresh_display = true;
+            }
+            Instruction::Ret => {
+                next_pc = self.pop_stack()?;
+            }
+            Instruction::Jp(addr) => next_pc = addr,
+            Instruction::Call(addr) => {
+                self.push_stack(next_pc)?;
+                next_pc = addr;
+            }
+            Instruction::SeImm { x, kk } => {
+                if self.v[x as usize] == kk {
+                    next_pc = self.skip_from(next_pc);
+                }
+            }
+            Instruction::SneImm { x, kk } => {
+                if self.v[x as usize] != kk {
+                    next_pc = self.skip_from(next_pc);
+                }
+            }
+            Instruction::SeReg { x, y } => {
+                if self.v[x as usize] == self.v[y as usize] {
+                    next_pc = self.skip_from(next_pc);
+                }
+            }
+            Instruction::SaveRange { x, y } => {
+                for (offset, register) in Self::register_range(x, y).enumerate() {
+                    self.write_memory(self.i as usize + offset, self.v[register])?;
+                }
+            }
+            Instruction::LoadRange { x, y } => {
+                for (offset, register) in Self::register_range(x, y).enumerate() {
+                    self.v[register] = self.read_memory(self.i as usize + offset)?;
+                }
+            }
+            Instruction::LdImm { x, kk } => self.v[x as usize] = kk,
+            Instruction::AddImm { x, kk } => {
+                self.v[x as usize] = u8::wrapping_add(self.v[x as usize], kk);
+            }
+            Instruction::LdReg { x, y } => self.v[x as usize] = self.v[y as usize],
+            Instruction::Or { x, y } => {
+                self.v[x as usize] |= self.v[y as usize];
+                self.reset_vf();
+            }
+            Instruction::And { x, y } => {
+                self.v[x as usize] &= self.v[y as usize];
+                self.reset_vf();
+            }
+            Instruction::Xor { x, y } => {
+                self.v[x as usize] ^= self.v[y as usize];
+                self.reset_vf();
+            }
+            // VF is written last so the flag wins when X is F
+            Instruction::Add { x, y } => {
+                let (sum, carry) = self.v[x as usize].overflowing_add(self.v[y as usize]);
+                self.v[x as usize] = sum;
+                self.v[0xF] = if carry { 1 } else { 0 };
+            }
+            Instruction::Sub { x, y } => {
+                let (difference, borrow) = self.v[x as usize].overflowing_sub(self.v[y as usize]);
+                self.v[x as usize] = difference;
+                self.v[0xF] = if borrow { 0 } else { 1 };
+            }
+            Instruction::Shr { x, y } => {
+                let value = self.shift_source(x, y);
+                let least_significant = get_bit_at(value, 0);
+                self.v[x as usize] = value >> 1;
+                self.v[0xF] = if least_significant { 1 } else { 0 };
+            }
+            Instruction::Subn { x, y } => {
+                let (difference, borrow) = self.v[y as usize].overflowing_sub(self.v[x as usize]);
+                self.v[x as usize] = difference;
+                self.v[0xF] = if borrow { 0 } else { 1 };
+            }
+            Instruction::Shl { x, y } => {
+                let value = self.shift_source(x, y);
+                let most_significant = get_bit_at(value, 7);
+                self.v[x as usize] = value << 1;
+                self.v[0xF] = if most_significant { 1 } else { 0 };
+            }
+            Instruction::SneReg { x, y } => {
+                if self.v[x as usize] != self.v[y as usize] {
+                    next_pc = self.skip_from(next_pc);
+                }
+            }
+            Instruction::LdI(addr) => self.i = addr,
+            Instruction::JpV0(addr) => {
+                let register = if self.quirks.jump_uses_vx {
+                    (addr >> 8) as usize
+                } else {
+                    0
+                };
+                next_pc = addr + self.v[register] as u16;
+            }
+            Instruction::Rnd { x, kk } => {
+                self.v[x as usize] = self.rng.next_u8() & kk;
+            }
+            Instruction::Drw { x, y, n } => {
+                self.draw_sprite(self.v[x as usize], self.v[y as usize], n)?;
+                refresh_display = true;
+            }
+            // Like the VIP, only the low nibble of VX selects the key
+            Instruction::Skp(x) => {
+                if self.keyboard[(self.v[x as usize] & 0xF) as usize] {
+                    next_pc = self.skip_from(next_pc);
+                }
+            }
+            Instruction::Sknp(x) => {
+                if !self.keyboard[(self.v[x as usize] & 0xF) as usize] {
+                    next_pc = self.skip_from(next_pc);
+                }
+            }
+            Instruction::LdILong(addr) => self.i = addr,
+            Instruction::Plane(n) => self.planes = n & (PLANE_1 | PLANE_2),
+            Instruction::Audio => {
+                for k in 0..self.audio_pattern.len() {
+                    self.audio_pattern[k] = self.read_memory(self.i as usize + k)?;
+                }
+                self.audio_pattern_loaded = true;
+            }
+            Instruction::LdVxDt(x) => self.v[x as usize] = self.dt,
+            Instruction::LdVxK(x) => {
+                self.state = ExecutionState::WaitingForKey {
+                    register: x,
+                    key: None,
+                };
+                wait_for_keyboard = Some(x as usize);
+            }
+            Instruction::LdDtVx(x) => self.dt = self.v[x as usize],
+            Instruction::LdStVx(x) => self.st = self.v[x as usize],
+            Instruction::AddI(x) => self.i = u16::wrapping_add(self.i, self.v[x as usize] as u16),
+            Instruction::LdF(x) => self.i = FONT_START as u16 + (self.v[x as usize] as u16 * 5),
+            Instruction::LdHf(x) => {
+                self.i = BIG_FONT_START as u16 + (self.v[x as usize] as u16 * 10)
+            }
+            Instruction::Pitch(x) => self.pitch = self.v[x as usize],
+            Instruction::LdB(x) => {
+                let mut value = self.v[x as usize];
+                self.write_memory(self.i as usize + 2, value % 10)?;
+                value /= 10;
+                self.write_memory(self.i as usize + 1, value % 10)?;
+                value /= 10;
+                self.write_memory(self.i as usize, value % 10)?;
+            }
+            Instruction::LdIVx(x) => {
+                for i in 0..=x as usize {
+                    self.write_memory(self.i as usize + i, self.v[i])?;
+                }
+                if self.quirks.load_store_increments_i {
+                    self.i = self.i.wrapping_add(x as u16 + 1);
+                }
+            }
+            Instruction::LdVxI(x) => {
+                for i in 0..=x as usize {
+                    self.v[i] = self.read_memory(self.i as usize + i)?;
+                }
+                if self.quirks.load_store_increments_i {
+                    self.i = self.i.wrapping_add(x as u16 + 1);
+                }
+            }
+            Instruction::LdRVx(x) => {
+                self.rpl[..=x as usize].copy_from_slice(&self.v[..=x as usize]);
+            }
+            Instruction::LdVxR(x) => {
+                self.v[..=x as usize].copy_from_slice(&self.rpl[..=x as usize]);
+            }
+            // 0NNN calls a native machine code routine, which we can't run
+            Instruction::Sys(_) | Instruction::Unknown(_) => return Err(self.unknown_op(op_code)),
+        }
+
+        self.pc = next_pc;
+        Ok(InterpreterResult {
+            refresh_display,
+            wait_for_keyboard,
+        })
+    }
+
+    fn draw_sprite(&mut self, x: u8, y: u8, n: u8) -> Result<(), ExecError> {
+        // DXY0 draws a 16x16 sprite stored as two bytes per row, or nothing on the VIP
+        let (length, width) = match n {
+            0 if self.quirks.schip_opcodes => (32, 16),
+            n => (n as usize, 8),
+        };
+        let mut start = self.i as usize;
+        let mut rows_collided = 0;
+        let mut rows_clipped = 0;
+
+        // With both XO-CHIP planes selected, the second plane's sprite follows the first's
+        for plane in [PLANE_1, PLANE_2] {
+            if self.planes & plane == 0 {
+                continue;
+            }
+
+            let sprite = (start..start + length)
+                .map(|addr| self.read_memory(addr))
+                .collect::<Result<Vec<u8>, _>>()?;
+            let rows: Vec<u16> = if width == 16 {
+                sprite
+                    .chunks(2)
+                    .map(|row| (row[0] as u16) << 8 | row[1] as u16)
+                    .collect()
+            } else {
+                sprite.iter().map(|&row| (row as u16) << 8).collect()
+            };
+
+            let collision = self.framebuffer.xor_sprite_rows(
+                x as usize,
+                y as usize,
+                &rows,
+                width,
+                plane,
+                self.quirks.clip_sprites,
+            );
+            rows_collided += collision.rows_collided;
+            rows_clipped = collision.rows_clipped;
+            start += length;
+        }
+
+        self.v[0xF] = if self.quirks.count_collided_rows && self.is_hires() {
+            (rows_collided + rows_clipped) as u8
+        } else if rows_collided > 0 {
+            1
+        } else {
+            0
+        };
+        self.mirror_display();
+        Ok(())
+    }
+
+    /// Registers VX to VY in the order `5XY2`/`5XY3` transfer them, which is
+    /// descending when X > Y
+    fn register_range(x: u8, y: u8) -> Box<dyn Iterator<Item = usize>> {
+        if x <= y {
+            Box::new(x as usize..=y as usize)
+        } else {
+            Box::new((y as usize..=x as usize).rev())
+        }
+    }
+
+    fn is_hires(&self) -> bool {
+        self.framebuffer.width() == HIRES_DISPLAY_WIDTH
+    }
+
+    /// Copies the display to RAM at `DISPLAY_MEM_START` for ROMs that read it
+    fn mirror_display(&mut self) {
+        // The VIP only ever had the low resolution display
+        if self.quirks.vip_memory_layout && !self.is_hires() {
+            let packed = self.framebuffer.to_packed_bytes();
+            for (k, byte) in packed.into_iter().enumerate() {
+                // The whole display is copied every time, so only changes are writes
+                let addr = DISPLAY_MEM_START + k;
+                if self.memory[addr] != byte {
+                    self.write_memory(addr, byte)
+                        .expect("display memory is within RAM");
+                }
+            }
+        }
+    }
+}
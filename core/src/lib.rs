@@ -0,0 +1,42 @@
+//! Platform independent CHIP-8 interpreter core.
+//!
+//! This crate has no windowing or graphics dependencies: frontends feed it a
+//! ROM, drive [`Interpreter::run_frame`] at 60 Hz and present the
+//! [`Framebuffer`] however they like.
+
+mod analysis;
+pub mod assembler;
+pub mod audio;
+pub mod constants;
+pub mod debugger;
+pub mod disassembler;
+pub mod error;
+pub mod framebuffer;
+pub mod gdb;
+pub mod instruction;
+pub mod interpreter;
+pub mod movie;
+pub mod quirks;
+pub mod rewind;
+pub mod rng;
+pub mod stack;
+pub mod state;
+pub mod util;
+pub mod watch;
+
+pub use assembler::Assembler;
+pub use audio::{AudioFrame, AudioSink, Buzzer, WavSink};
+pub use debugger::{Command, DebugEvent, Debugger};
+pub use disassembler::{DisasmLine, Disassembler};
+pub use error::{AsmError, ExecError, LoadError, MovieError, StateError};
+pub use framebuffer::{Collision, Framebuffer, PLANE_1, PLANE_2};
+pub use gdb::GdbStub;
+pub use instruction::{decode, decode_at, decode_for, encode, encode_bytes, Instruction};
+pub use interpreter::Interpreter;
+pub use movie::{Movie, MovieFrame};
+pub use quirks::Quirks;
+pub use rewind::Rewind;
+pub use rng::Rng;
+pub use stack::CallStack;
+pub use util::{ExecutionState, InterpreterResult, OpCode};
+pub use watch::{Access, WatchHit, Watchpoint};
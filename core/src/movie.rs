@@ -0,0 +1,229 @@
+//! Input movies: the keypad state of every frame, enough to replay a run exactly.
+//!
+//! Movies are text files so testers can attach them to bug reports:
+//!
+//! ```text
+//! chip8-movie 2
+//! rom 8f3a5c1d2e4b6a70
+//! seed 42
+//! quirks 3b000c0000100000
+//! ipf 11
+//! frames
+//! 0000 5f1e2d3c4b5a6978
+//! 0020
+//! ```
+//!
+//! The header holds the ROM hash, the `CXNN` seed, the quirks encoded like in a save
+//! state and the instructions per frame. Each frame line has the keypad bitmask, see
+//! [`Interpreter::keys`], optionally followed by the [`Interpreter::state_hash`]
+//! after the frame ran, which playback compares against to catch desyncs.
+
+use crate::error::{ExecError, MovieError};
+use crate::interpreter::Interpreter;
+use crate::quirks::Quirks;
+use crate::state::{self, StateReader, StateWriter};
+use crate::util::InterpreterResult;
+use std::fmt;
+use std::str::FromStr;
+
+pub const MOVIE_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    pub rom_hash: u64,
+    pub seed: u64,
+    pub quirks: Quirks,
+    pub instructions_per_frame: usize,
+    pub frames: Vec<MovieFrame>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MovieFrame {
+    pub keys: u16,
+    pub state_hash: Option<u64>,
+}
+
+impl Movie {
+    pub fn new(program: &[u8], quirks: Quirks, seed: u64, instructions_per_frame: usize) -> Self {
+        Movie {
+            rom_hash: state::rom_hash(program),
+            seed,
+            quirks,
+            instructions_per_frame,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Creates an interpreter in the state the movie starts from
+    pub fn interpreter(&self, program: Vec<u8>) -> Result<Interpreter, MovieError> {
+        let found = state::rom_hash(&program);
+        if found != self.rom_hash {
+            return Err(MovieError::RomMismatch {
+                expected: found,
+                found: self.rom_hash,
+            });
+        }
+        let mut interpreter = Interpreter::new(program, self.quirks)?;
+        interpreter.set_seed(self.seed);
+        Ok(interpreter)
+    }
+
+    /// Sets the keypad, runs a frame and appends it to the movie. The frame is kept
+    /// even if the interpreter fails, so the movie reproduces the crash.
+    pub fn record_frame(
+        &mut self,
+        interpreter: &mut Interpreter,
+        keys: u16,
+        hash_state: bool,
+    ) -> Result<InterpreterResult, ExecError> {
+        interpreter.set_keys(keys);
+        let result = interpreter.run_frame(self.instructions_per_frame);
+        self.frames.push(MovieFrame {
+            keys,
+            state_hash: (hash_state && result.is_ok()).then(|| interpreter.state_hash()),
+        });
+        result
+    }
+
+    /// Replays frame `frame` of the movie, checking the state hash if there is one.
+    /// Fails without running anything past the last frame.
+    pub fn play_frame(
+        &self,
+        interpreter: &mut Interpreter,
+        frame: usize,
+    ) -> Result<InterpreterResult, MovieError> {
+        let movie_frame = *self.frames.get(frame).ok_or(MovieError::EndOfMovie {
+            frame,
+            length: self.len(),
+        })?;
+        interpreter.set_keys(movie_frame.keys);
+        let result = interpreter.run_frame(self.instructions_per_frame)?;
+
+        if let Some(expected) = movie_frame.state_hash {
+            let found = interpreter.state_hash();
+            if found != expected {
+                return Err(MovieError::Desync {
+                    frame,
+                    expected,
+                    found,
+                });
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+impl fmt::Display for Movie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut quirks = StateWriter::default();
+        quirks.quirks(&self.quirks).map_err(|_| fmt::Error)?;
+
+        writeln!(f, "chip8-movie {}", MOVIE_VERSION)?;
+        writeln!(f, "rom {:016x}", self.rom_hash)?;
+        writeln!(f, "seed {}", self.seed)?;
+        write!(f, "quirks ")?;
+        for byte in quirks.finish() {
+            write!(f, "{:02x}", byte)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "ipf {}", self.instructions_per_frame)?;
+        writeln!(f, "frames")?;
+        for frame in &self.frames {
+            match frame.state_hash {
+                Some(hash) => writeln!(f, "{:04x} {:016x}", frame.keys, hash)?,
+                None => writeln!(f, "{:04x}", frame.keys)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Movie {
+    type Err = MovieError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate();
+        let mut header_lines = 0;
+        let mut header = |key: &str| {
+            // A missing line is reported where it was expected
+            header_lines += 1;
+            let (_, line) = lines
+                .next()
+                .ok_or(MovieError::Invalid { line: header_lines })?;
+            let (name, value) = line.split_once(' ').unwrap_or((line, ""));
+            if name != key {
+                return Err(MovieError::Invalid { line: header_lines });
+            }
+            Ok((header_lines, value.trim()))
+        };
+
+        let (line, version) = header("chip8-movie")?;
+        if version.parse() != Ok(MOVIE_VERSION) {
+            return Err(MovieError::Invalid { line });
+        }
+        let (line, rom_hash) = header("rom")?;
+        let rom_hash =
+            u64::from_str_radix(rom_hash, 16).map_err(|_| MovieError::Invalid { line })?;
+        let (line, seed) = header("seed")?;
+        let seed = seed.parse().map_err(|_| MovieError::Invalid { line })?;
+        let (line, quirks) = header("quirks")?;
+        let quirks = parse_hex(quirks)
+            .and_then(|bytes| {
+                let mut reader = StateReader::new(&bytes);
+                let quirks = reader.quirks().ok()?;
+                reader.finish().ok().map(|_| quirks)
+            })
+            .ok_or(MovieError::Invalid { line })?;
+        let (line, ipf) = header("ipf")?;
+        let instructions_per_frame = ipf.parse().map_err(|_| MovieError::Invalid { line })?;
+        let (line, rest) = header("frames")?;
+        if !rest.is_empty() {
+            return Err(MovieError::Invalid { line });
+        }
+
+        let frames = lines
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(k, line)| {
+                let mut fields = line.split_whitespace();
+                let keys = fields
+                    .next()
+                    .and_then(|keys| u16::from_str_radix(keys, 16).ok());
+                let state_hash = fields.next().map(|hash| u64::from_str_radix(hash, 16).ok());
+                match (keys, state_hash, fields.next()) {
+                    (Some(keys), None, None) => Ok(MovieFrame {
+                        keys,
+                        state_hash: None,
+                    }),
+                    (Some(keys), Some(Some(hash)), None) => Ok(MovieFrame {
+                        keys,
+                        state_hash: Some(hash),
+                    }),
+                    _ => Err(MovieError::Invalid { line: k + 1 }),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Movie {
+            rom_hash,
+            seed,
+            quirks,
+            instructions_per_frame,
+            frames,
+        })
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|k| u8::from_str_radix(hex.get(k..k + 2)?, 16).ok())
+        .collect()
+}
@@ -0,0 +1,154 @@
+use crate::constants::{MEMORY_SIZE, XO_CHIP_MEMORY_SIZE};
+use crate::error::LoadError;
+use std::str::FromStr;
+
+/// Behaviors that differ between CHIP-8 implementations.
+///
+/// ROMs are usually written against one particular interpreter, so the
+/// profile has to match the ROM for it to run correctly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// `8XY6`/`8XYE` shift VY into VX instead of shifting VX in place
+    pub shift_uses_vy: bool,
+    /// `FX55`/`FX65` leave I pointing past the last register transferred
+    pub load_store_increments_i: bool,
+    /// `BNNN` jumps to NNN + VX (where X is the high nibble of NNN) instead of NNN + V0
+    pub jump_uses_vx: bool,
+    /// `8XY1`/`8XY2`/`8XY3` reset VF to 0
+    pub vf_reset: bool,
+    /// Sprites are clipped at the screen edges instead of wrapping around
+    pub clip_sprites: bool,
+    /// `DXYN` waits for the vertical blank, so at most one sprite is drawn per frame
+    pub display_wait: bool,
+    /// In hi-res mode `DXYN` sets VF to the number of sprite rows that collided or were
+    /// clipped off the bottom instead of just 1
+    pub count_collided_rows: bool,
+    /// Maximum number of nested subroutine calls
+    pub stack_depth: usize,
+    /// Bytes of RAM, 64 KiB on XO-CHIP
+    pub memory_size: usize,
+    /// Keep the call stack in RAM at `STACK_START` like the VIP did, for ROMs that
+    /// inspect or patch it. The stack then ends at the display, after 48 calls.
+    pub vip_memory_layout: bool,
+    /// Run the SUPER-CHIP instructions: hi-res, scrolling, `DXY0` 16x16 sprites, the
+    /// big font and the RPL flags. Without them `00FF` and friends are machine code
+    /// calls and `DXY0` draws nothing, like on the VIP.
+    pub schip_opcodes: bool,
+    /// Run the XO-CHIP instructions: `F000 NNNN`, planes, audio, `5XY2`/`5XY3` and
+    /// `00DN`. Skips then step over all 4 bytes of `F000 NNNN`, which is otherwise an
+    /// unknown op code like on the VIP.
+    pub xochip_opcodes: bool,
+}
+
+impl Quirks {
+    /// The original COSMAC VIP interpreter.
+    pub fn vip() -> Self {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            vf_reset: true,
+            clip_sprites: true,
+            display_wait: true,
+            count_collided_rows: false,
+            stack_depth: 12,
+            vip_memory_layout: false,
+            schip_opcodes: false,
+            xochip_opcodes: false,
+            memory_size: MEMORY_SIZE,
+        }
+    }
+
+    /// CHIP-48 on the HP-48 calculators.
+    pub fn chip48() -> Self {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: true,
+            vf_reset: false,
+            clip_sprites: true,
+            display_wait: false,
+            count_collided_rows: false,
+            stack_depth: 16,
+            vip_memory_layout: false,
+            schip_opcodes: false,
+            xochip_opcodes: false,
+            memory_size: MEMORY_SIZE,
+        }
+    }
+
+    /// SUPER-CHIP 1.1.
+    pub fn schip() -> Self {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: true,
+            vf_reset: false,
+            clip_sprites: true,
+            display_wait: false,
+            count_collided_rows: true,
+            stack_depth: 16,
+            vip_memory_layout: false,
+            schip_opcodes: true,
+            xochip_opcodes: false,
+            memory_size: MEMORY_SIZE,
+        }
+    }
+
+    /// XO-CHIP as implemented by Octo.
+    pub fn xochip() -> Self {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            vf_reset: false,
+            clip_sprites: false,
+            display_wait: false,
+            count_collided_rows: false,
+            stack_depth: 16,
+            vip_memory_layout: false,
+            schip_opcodes: true,
+            xochip_opcodes: true,
+            memory_size: XO_CHIP_MEMORY_SIZE,
+        }
+    }
+
+    /// Fails for quirks the interpreter can't run with or save: memory other than
+    /// the CHIP-8 or XO-CHIP sizes, or a stack deeper than a save state can hold.
+    pub fn check(&self) -> Result<(), LoadError> {
+        if ![MEMORY_SIZE, XO_CHIP_MEMORY_SIZE].contains(&self.memory_size) {
+            return Err(LoadError::UnsupportedMemorySize(self.memory_size));
+        }
+        let max = u16::MAX as usize;
+        if self.stack_depth > max {
+            return Err(LoadError::StackTooDeep {
+                depth: self.stack_depth,
+                max,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks::vip()
+    }
+}
+
+impl FromStr for Quirks {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "vip" | "chip8" | "chip-8" => Ok(Quirks::vip()),
+            "chip48" | "chip-48" => Ok(Quirks::chip48()),
+            "schip" | "superchip" | "super-chip" => Ok(Quirks::schip()),
+            "xochip" | "xo-chip" => Ok(Quirks::xochip()),
+            _ => Err(format!(
+                "unknown quirks profile '{}', expected one of vip, chip48, schip, xochip",
+                s
+            )),
+        }
+    }
+}
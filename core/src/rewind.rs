@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+
+/// Ring buffer of save states, one per frame, for running a program backwards.
+///
+/// Only the latest state is kept whole. Each older one is stored as the difference
+/// with the state that followed it: the two are XORed together and the runs of
+/// unchanged (zero) bytes are skipped, which keeps a frame down to a few bytes when
+/// little happened on screen.
+#[derive(Debug, Clone, Default)]
+pub struct Rewind {
+    capacity: usize,
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Delta>,
+}
+
+#[derive(Debug, Clone)]
+struct Delta {
+    // Length of the older state, which changes with the display resolution
+    length: usize,
+    // Alternating varint encoded counts of unchanged bytes and of changed bytes, the
+    // latter followed by the XORed bytes themselves
+    runs: Vec<u8>,
+}
+
+impl Rewind {
+    /// Keeps enough states to go back `capacity` frames
+    pub fn new(capacity: usize) -> Self {
+        Rewind {
+            capacity,
+            latest: None,
+            deltas: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Records the state of the frame that just ran, see
+    /// [`Interpreter::save_state`](crate::Interpreter::save_state)
+    pub fn record(&mut self, state: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some(latest) = self.latest.take() {
+            if self.deltas.len() == self.capacity {
+                self.deltas.pop_front();
+            }
+            self.deltas.push_back(Delta::between(&state, &latest));
+        }
+        self.latest = Some(state);
+    }
+
+    /// Steps one frame back, returning the state to load, or `None` once the oldest
+    /// recorded frame has been reached
+    pub fn step_back(&mut self) -> Option<&[u8]> {
+        let delta = self.deltas.pop_back()?;
+        let latest = self.latest.as_mut()?;
+        delta.apply(latest);
+        Some(latest)
+    }
+
+    /// Number of frames kept, 0 when rewinding is off and states needn't be saved
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of frames that can be stepped back
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+    }
+
+    /// Bytes used by the recorded states
+    pub fn memory_usage(&self) -> usize {
+        self.latest.as_ref().map_or(0, Vec::len)
+            + self
+                .deltas
+                .iter()
+                .map(|delta| delta.runs.len())
+                .sum::<usize>()
+    }
+}
+
+impl Delta {
+    /// Difference turning `newer` back into `older`
+    fn between(newer: &[u8], older: &[u8]) -> Self {
+        let byte = |state: &[u8], k: usize| state.get(k).copied().unwrap_or(0);
+        let length = newer.len().max(older.len());
+        let mut runs = Vec::new();
+        let mut k = 0;
+
+        while k < length {
+            let start = k;
+            while k < length && byte(newer, k) == byte(older, k) {
+                k += 1;
+            }
+            write_varint(&mut runs, k - start);
+
+            let start = k;
+            while k < length && byte(newer, k) != byte(older, k) {
+                k += 1;
+            }
+            write_varint(&mut runs, k - start);
+            runs.extend((start..k).map(|j| byte(newer, j) ^ byte(older, j)));
+        }
+
+        Delta {
+            length: older.len(),
+            runs,
+        }
+    }
+
+    fn apply(&self, state: &mut Vec<u8>) {
+        if state.len() < self.length {
+            state.resize(self.length, 0);
+        }
+
+        let mut runs = self.runs.iter().copied();
+        let mut k = 0;
+        while let Some(unchanged) = read_varint(&mut runs) {
+            k += unchanged;
+            let changed = read_varint(&mut runs).unwrap_or(0);
+            for (byte, mask) in state[k..k + changed].iter_mut().zip(&mut runs) {
+                *byte ^= mask;
+            }
+            k += changed;
+        }
+
+        state.truncate(self.length);
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &mut impl Iterator<Item = u8>) -> Option<usize> {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes.next()?;
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+        shift += 7;
+    }
+}
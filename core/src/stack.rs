@@ -0,0 +1,47 @@
+use crate::error::ExecError;
+
+/// The return addresses of the subroutines currently being executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallStack {
+    frames: Vec<u16>,
+    depth: usize,
+}
+
+impl CallStack {
+    pub fn new(depth: usize) -> Self {
+        CallStack {
+            frames: Vec::with_capacity(depth),
+            depth,
+        }
+    }
+
+    pub fn push(&mut self, addr: u16) -> Result<(), ExecError> {
+        if self.frames.len() >= self.depth {
+            return Err(ExecError::StackOverflow);
+        }
+        self.frames.push(addr);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<u16, ExecError> {
+        self.frames.pop().ok_or(ExecError::StackUnderflow)
+    }
+
+    /// Number of return addresses on the stack, also the stack pointer
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Return addresses, the innermost call last
+    pub fn frames(&self) -> &[u16] {
+        &self.frames
+    }
+}
@@ -0,0 +1,165 @@
+//! Binary save state format written by [`Interpreter::save_state`].
+//!
+//! A state starts with a header: the [`MAGIC`] bytes, the format [`VERSION`], the
+//! [`rom_hash`] of the program and the quirks it ran with. The machine state follows.
+//! Every integer is little endian.
+//!
+//! [`Interpreter::save_state`]: crate::Interpreter::save_state
+
+use crate::error::{LoadError, StateError};
+use crate::quirks::Quirks;
+
+pub const MAGIC: &[u8; 4] = b"CH8S";
+pub const VERSION: u16 = 5;
+
+/// FNV-1a hash, stable across platforms and builds
+pub fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Hash identifying a ROM
+pub fn rom_hash(program: &[u8]) -> u64 {
+    hash(program)
+}
+
+#[derive(Default)]
+pub(crate) struct StateWriter {
+    bytes: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// Fails instead of truncating quirks that [`Quirks::check`] refuses
+    pub fn quirks(&mut self, quirks: &Quirks) -> Result<(), LoadError> {
+        quirks.check()?;
+        let flags = [
+            quirks.shift_uses_vy,
+            quirks.load_store_increments_i,
+            quirks.jump_uses_vx,
+            quirks.vf_reset,
+            quirks.clip_sprites,
+            quirks.display_wait,
+            quirks.count_collided_rows,
+            quirks.vip_memory_layout,
+            quirks.xochip_opcodes,
+            quirks.schip_opcodes,
+        ];
+        self.u16(
+            flags
+                .iter()
+                .enumerate()
+                .fold(0, |bits, (k, &flag)| bits | (flag as u16) << k),
+        );
+        self.u16(quirks.stack_depth as u16);
+        self.u32(quirks.memory_size as u32);
+        Ok(())
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+pub(crate) struct StateReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        StateReader { bytes }
+    }
+
+    pub fn bytes(&mut self, length: usize) -> Result<&'a [u8], StateError> {
+        if self.bytes.len() < length {
+            return Err(StateError::Corrupted);
+        }
+        let (bytes, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    pub fn array<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.bytes(N)?);
+        Ok(array)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool, StateError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StateError::Corrupted),
+        }
+    }
+
+    pub fn u16(&mut self) -> Result<u16, StateError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, StateError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, StateError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    pub fn quirks(&mut self) -> Result<Quirks, StateError> {
+        let flags = self.u16()?;
+        let flag = |k: usize| flags & (1 << k) != 0;
+        let quirks = Quirks {
+            shift_uses_vy: flag(0),
+            load_store_increments_i: flag(1),
+            jump_uses_vx: flag(2),
+            vf_reset: flag(3),
+            clip_sprites: flag(4),
+            display_wait: flag(5),
+            count_collided_rows: flag(6),
+            vip_memory_layout: flag(7),
+            xochip_opcodes: flag(8),
+            schip_opcodes: flag(9),
+            stack_depth: self.u16()? as usize,
+            memory_size: self.u32()? as usize,
+        };
+        quirks.check().map_err(|_| StateError::Corrupted)?;
+        Ok(quirks)
+    }
+
+    /// Fails unless every byte has been read
+    pub fn finish(self) -> Result<(), StateError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(StateError::Corrupted)
+        }
+    }
+}
@@ -0,0 +1,45 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpCode {
+    pub first: u8,
+    pub second: u8,
+}
+
+impl OpCode {
+    pub fn from_u16(raw: u16) -> Self {
+        OpCode {
+            first: (raw >> 8) as u8,
+            second: (raw & 0xFF) as u8,
+        }
+    }
+
+    pub fn to_u16(&self) -> u16 {
+        ((self.first as u16) << 8) | self.second as u16
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterpreterResult {
+    pub refresh_display: bool,
+    pub wait_for_keyboard: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionState {
+    Running,
+    /// `FX0A` is blocking until a key is pressed and released again, `key` holds the
+    /// key once it has been pressed
+    WaitingForKey {
+        register: u8,
+        key: Option<u8>,
+    },
+    /// The program ran `00FD` and won't execute anything else
+    Exited,
+}
+
+pub fn get_bit_at(input: u8, n: u8) -> bool {
+    if n < 8 {
+        input & (1 << n) != 0
+    } else {
+        false
+    }
+}
@@ -0,0 +1,86 @@
+//! Watchpoints: catching the program as it reads or writes a range of RAM.
+//!
+//! Every access the program makes goes through the interpreter's memory helpers,
+//! including instruction fetch, `DXYN` sprite reads and the `FX33`/`FX55`/`FX65`
+//! transfers. Those record a [`WatchHit`] whenever a [`Watchpoint`] covers the
+//! address, for debuggers to pick up with [`Interpreter::take_watch_hits`].
+//!
+//! [`Interpreter::take_watch_hits`]: crate::Interpreter::take_watch_hits
+
+use std::fmt;
+
+/// Which memory accesses a watchpoint reacts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Access {
+    /// Whether a watchpoint for `self` fires on `access`
+    pub fn matches(self, access: Access) -> bool {
+        self == Access::ReadWrite || self == access
+    }
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Access::Read => "read",
+            Access::Write => "write",
+            Access::ReadWrite => "read/write",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub addr: usize,
+    pub length: usize,
+    pub access: Access,
+}
+
+impl Watchpoint {
+    pub fn new(addr: usize, length: usize, access: Access) -> Self {
+        Watchpoint {
+            addr,
+            length: length.max(1),
+            access,
+        }
+    }
+
+    pub fn contains(&self, addr: usize) -> bool {
+        addr >= self.addr && addr - self.addr < self.length
+    }
+}
+
+/// A memory access that hit a watchpoint. `old` and `new` are the same for reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit {
+    /// Address of the instruction that made the access
+    pub pc: u16,
+    pub addr: usize,
+    /// [`Access::Read`] or [`Access::Write`]
+    pub access: Access,
+    pub old: u8,
+    pub new: u8,
+}
+
+impl fmt::Display for WatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.access {
+            Access::Read => write!(
+                f,
+                "read {:02x} from {:03x} at pc {:03x}",
+                self.old, self.addr, self.pc
+            ),
+            _ => write!(
+                f,
+                "wrote {:03x} at pc {:03x}: {:02x} -> {:02x}",
+                self.addr, self.pc, self.old, self.new
+            ),
+        }
+    }
+}
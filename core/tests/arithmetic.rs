@@ -0,0 +1,135 @@
+use chip8_core::{Interpreter, Quirks};
+
+/// Loads `vx` into VX and `vy` into VY, then runs `op` on them.
+fn run(op: u16, x: u8, y: u8, vx: u8, vy: u8) -> [u8; 16] {
+    let mut program = vec![0x60 | y, vy, 0x60 | x, vx];
+    program.extend_from_slice(&op.to_be_bytes());
+
+    let mut interpreter = Interpreter::new(program, Quirks::default()).unwrap();
+    for _ in 0..3 {
+        interpreter.tick().unwrap();
+    }
+    *interpreter.registers()
+}
+
+fn add(vx: u8, vy: u8) -> [u8; 16] {
+    run(0x8014, 0, 1, vx, vy)
+}
+
+fn sub(vx: u8, vy: u8) -> [u8; 16] {
+    run(0x8015, 0, 1, vx, vy)
+}
+
+fn subn(vx: u8, vy: u8) -> [u8; 16] {
+    run(0x8017, 0, 1, vx, vy)
+}
+
+#[test]
+fn add_without_carry() {
+    let v = add(0x12, 0x34);
+    assert_eq!(v[0], 0x46);
+    assert_eq!(v[0xF], 0);
+}
+
+#[test]
+fn add_with_carry() {
+    let v = add(0xff, 0x02);
+    assert_eq!(v[0], 0x01);
+    assert_eq!(v[0xF], 1);
+}
+
+#[test]
+fn add_to_exactly_256_carries() {
+    let v = add(0x80, 0x80);
+    assert_eq!(v[0], 0x00);
+    assert_eq!(v[0xF], 1);
+}
+
+#[test]
+fn add_clears_stale_carry() {
+    let program = vec![0x6f, 0x01, 0x60, 0x01, 0x61, 0x01, 0x80, 0x14];
+    let mut interpreter = Interpreter::new(program, Quirks::default()).unwrap();
+    for _ in 0..4 {
+        interpreter.tick().unwrap();
+    }
+    assert_eq!(interpreter.registers()[0], 2);
+    assert_eq!(interpreter.registers()[0xF], 0);
+}
+
+#[test]
+fn sub_without_borrow() {
+    let v = sub(0x34, 0x12);
+    assert_eq!(v[0], 0x22);
+    assert_eq!(v[0xF], 1);
+}
+
+#[test]
+fn sub_equal_operands_is_not_a_borrow() {
+    let v = sub(0x42, 0x42);
+    assert_eq!(v[0], 0x00);
+    assert_eq!(v[0xF], 1);
+}
+
+#[test]
+fn sub_with_borrow() {
+    let v = sub(0x12, 0x34);
+    assert_eq!(v[0], 0xde);
+    assert_eq!(v[0xF], 0);
+}
+
+#[test]
+fn subn_without_borrow() {
+    let v = subn(0x12, 0x34);
+    assert_eq!(v[0], 0x22);
+    assert_eq!(v[0xF], 1);
+}
+
+#[test]
+fn subn_equal_operands_is_not_a_borrow() {
+    let v = subn(0x42, 0x42);
+    assert_eq!(v[0], 0x00);
+    assert_eq!(v[0xF], 1);
+}
+
+#[test]
+fn subn_with_borrow() {
+    let v = subn(0x34, 0x12);
+    assert_eq!(v[0], 0xde);
+    assert_eq!(v[0xF], 0);
+}
+
+#[test]
+fn add_into_vf_keeps_the_flag() {
+    // 8F14: VF = VF + V1, the carry overwrites the sum
+    let v = run(0x8f14, 0xf, 1, 0xff, 0x02);
+    assert_eq!(v[0xF], 1);
+
+    let v = run(0x8f14, 0xf, 1, 0x10, 0x02);
+    assert_eq!(v[0xF], 0);
+}
+
+#[test]
+fn sub_into_vf_keeps_the_flag() {
+    let v = run(0x8f15, 0xf, 1, 0x10, 0x02);
+    assert_eq!(v[0xF], 1);
+
+    let v = run(0x8f15, 0xf, 1, 0x02, 0x10);
+    assert_eq!(v[0xF], 0);
+}
+
+#[test]
+fn subn_into_vf_keeps_the_flag() {
+    let v = run(0x8f17, 0xf, 1, 0x02, 0x10);
+    assert_eq!(v[0xF], 1);
+
+    let v = run(0x8f17, 0xf, 1, 0x10, 0x02);
+    assert_eq!(v[0xF], 0);
+}
+
+#[test]
+fn vf_as_operand_is_read_before_the_flag_is_written() {
+    // 80F4: V0 = V0 + VF
+    let v = run(0x80f4, 0, 0xf, 0x01, 0xff);
+    assert_eq!(v[0], 0x00);
+    assert_eq!(v[0xF], 1);
+}
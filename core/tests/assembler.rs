@@ -0,0 +1,115 @@
+use chip8_core::{decode, encode_bytes, AsmError, Assembler, Disassembler, OpCode, Rng};
+
+fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    Assembler::default().assemble(source)
+}
+
+#[test]
+fn assembles_what_the_disassembler_writes() {
+    let disassembler = Disassembler::default();
+    for raw in 0..=0xffffu16 {
+        let instruction = decode(&OpCode::from_u16(raw));
+        let source = disassembler.format(&instruction);
+        assert_eq!(
+            assemble(&source),
+            Ok(encode_bytes(&instruction)),
+            "{:04x} {}",
+            raw,
+            source
+        );
+    }
+}
+
+#[test]
+fn resolves_labels_and_constants() {
+    let source = "
+        SPEED EQU 2
+        HEIGHT equ $3
+
+        start:  LD I, sprite    ; labels can be used before they're defined
+                DRW v0, v1, HEIGHT
+                ADD v0, SPEED
+                JP start
+        sprite: db 0xff, %10000001, 255
+        table:  dw 0x1234, sprite
+    ";
+    assert_eq!(
+        assemble(source),
+        Ok(vec![
+            0xa2, 0x08, 0xd0, 0x13, 0x70, 0x02, 0x12, 0x00, 0xff, 0x81, 0xff, 0x12, 0x34, 0x02,
+            0x08,
+        ])
+    );
+}
+
+#[test]
+fn reads_addresses_as_hex() {
+    assert_eq!(
+        assemble("JP 2a4\nLD I, LONG 1234\nld va, 10\nLD I, #300"),
+        Ok(vec![
+            0x12, 0xa4, 0xf0, 0x00, 0x12, 0x34, 0x6a, 0x0a, 0xa3, 0x00
+        ])
+    );
+}
+
+#[test]
+fn reports_errors_with_their_line() {
+    assert_eq!(
+        assemble("CLS\nMOV v0, v1"),
+        Err(AsmError::UnknownMnemonic {
+            line: 2,
+            mnemonic: "MOV".to_string()
+        })
+    );
+    assert_eq!(
+        assemble("DRW v0, v1"),
+        Err(AsmError::InvalidOperands { line: 1 })
+    );
+    assert_eq!(
+        assemble("\nJP nowhere"),
+        Err(AsmError::UndefinedSymbol {
+            line: 2,
+            name: "nowhere".to_string()
+        })
+    );
+    assert_eq!(
+        assemble("a:\na:"),
+        Err(AsmError::DuplicateSymbol {
+            line: 2,
+            name: "a".to_string()
+        })
+    );
+    assert_eq!(
+        assemble("LD v0, 256"),
+        Err(AsmError::InvalidValue {
+            line: 1,
+            value: "256".to_string()
+        })
+    );
+}
+
+/// A ROM of random words, with jumps, calls and `LD I` aimed inside it so the
+/// listing has labels
+fn random_program(rng: &mut Rng) -> Vec<u8> {
+    let length = rng.next_u64() as usize % 300;
+    let mut program: Vec<u8> = (0..length).map(|_| rng.next_u8()).collect();
+    for word in program.chunks_exact_mut(2) {
+        if matches!(word[0] >> 4, 0x1 | 0x2 | 0xa | 0xb) && length > 0 {
+            let target = 0x200 + rng.next_u64() as usize % length;
+            word[0] = word[0] & 0xf0 | (target >> 8) as u8;
+            word[1] = target as u8;
+        }
+    }
+    program
+}
+
+#[test]
+fn round_trips_any_rom() {
+    let disassembler = Disassembler::default();
+    let mut rng = Rng::new(0x5eed);
+    for _ in 0..2000 {
+        let program = random_program(&mut rng);
+        let listing = disassembler.listing(&program);
+        assert_eq!(assemble(&listing).as_ref(), Ok(&program), "\n{}", listing);
+    }
+}
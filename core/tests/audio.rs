@@ -0,0 +1,99 @@
+use chip8_core::{AudioFrame, AudioSink, Buzzer, Interpreter, Quirks, WavSink};
+use std::cell::RefCell;
+use std::io::Cursor;
+use std::rc::Rc;
+
+struct Recorder(Rc<RefCell<Vec<AudioFrame>>>);
+
+impl AudioSink for Recorder {
+    fn play(&mut self, frame: &AudioFrame) {
+        self.0.borrow_mut().push(*frame);
+    }
+}
+
+fn tone() -> AudioFrame {
+    AudioFrame {
+        playing: true,
+        ..AudioFrame::silent()
+    }
+}
+
+#[test]
+fn sink_hears_every_frame_of_the_sound_timer() {
+    // V0 = 2, LD ST v0, JP 204
+    let program = vec![0x60, 0x02, 0xf0, 0x18, 0x12, 0x04];
+    let mut interpreter = Interpreter::new(program, Quirks::default()).unwrap();
+    let frames = Rc::new(RefCell::new(Vec::new()));
+    interpreter.set_audio_sink(Box::new(Recorder(frames.clone())));
+
+    for _ in 0..4 {
+        interpreter.run_frame(10).unwrap();
+    }
+
+    let playing: Vec<bool> = frames.borrow().iter().map(|f| f.playing).collect();
+    assert_eq!(playing, [true, true, false, false]);
+}
+
+#[test]
+fn xo_chip_pattern_is_only_used_once_loaded() {
+    // I = 300, AUDIO
+    let program = vec![0xa3, 0x00, 0xf0, 0x02];
+    let mut interpreter = Interpreter::new(program, Quirks::xochip()).unwrap();
+    assert_eq!(interpreter.audio_frame().pattern, None);
+
+    interpreter.tick().unwrap();
+    interpreter.tick().unwrap();
+    assert_eq!(interpreter.audio_frame().pattern, Some([0; 16]));
+}
+
+#[test]
+fn buzzer_renders_a_square_wave() {
+    let mut buzzer = Buzzer::new(8800).with_frequency(440.0).with_volume(0.5);
+    let samples = buzzer.render(&tone());
+
+    assert_eq!(samples.len(), 146);
+    // 20 samples per period, half of them high
+    assert!(samples[..10].iter().all(|&s| s == 0.5));
+    assert!(samples[10..20].iter().all(|&s| s == -0.5));
+    assert!(buzzer
+        .render(&AudioFrame::silent())
+        .iter()
+        .all(|&s| s == 0.0));
+}
+
+#[test]
+fn buzzer_plays_the_xo_chip_pattern() {
+    let mut pattern = [0; 16];
+    pattern[0] = 0b1010_0000;
+    let frame = AudioFrame {
+        pattern: Some(pattern),
+        ..tone()
+    };
+    // The default pitch plays 4000 bits per second
+    let mut buzzer = Buzzer::new(4000).with_volume(1.0);
+    let samples = buzzer.render(&frame);
+
+    assert_eq!(samples[..4], [1.0, -1.0, 1.0, -1.0]);
+    assert!(samples[4..].iter().all(|&s| s == -1.0));
+}
+
+#[test]
+fn wav_sink_writes_a_valid_file_after_every_frame() {
+    let mut sink = WavSink::new(Cursor::new(Vec::new()), Buzzer::new(6000)).unwrap();
+    sink.play(&tone());
+    sink.play(&AudioFrame::silent());
+    assert!(sink.error().is_none());
+
+    let wav = sink.into_inner().into_inner();
+    let data_length = 2 * 100 * 2;
+    assert_eq!(wav.len(), 44 + data_length);
+    assert_eq!(&wav[..4], b"RIFF");
+    assert_eq!(wav[4..8], (36 + data_length as u32).to_le_bytes());
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(wav[24..28], 6000u32.to_le_bytes());
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(wav[40..44], (data_length as u32).to_le_bytes());
+    // The tone starts high, the silent frame is all zeroes
+    assert!(i16::from_le_bytes([wav[44], wav[45]]) > 0);
+    assert!(wav[44 + 200..].iter().all(|&b| b == 0));
+}
@@ -0,0 +1,153 @@
+//! Well-known test ROMs from `tests/roms` run through the core and compared against
+//! golden framebuffers in `tests/golden`. ROMs that draw the same on every quirk
+//! profile have a single golden, the quirks test has one per profile.
+//!
+//! The goldens come from a reference emulator, the `c8` crate, not from this one.
+//! `tests/golden/record.sh` records them with the same frames, speed and keys as
+//! the tests below, so a change to either has to be made to both. Run it with
+//! `--check` to compare its recordings with the checked-in goldens.
+
+use chip8_core::Instruction::Jp;
+use chip8_core::{decode_at, Framebuffer, Interpreter, Quirks};
+use std::fs;
+use std::path::PathBuf;
+
+type Profile = (&'static str, fn() -> Quirks);
+
+const PROFILES: [Profile; 4] = [
+    ("vip", Quirks::vip),
+    ("chip48", Quirks::chip48),
+    ("schip", Quirks::schip),
+    ("xochip", Quirks::xochip),
+];
+
+const TEST_SUITE: &str = "chip8-test-suite.ch8";
+
+/// Byte the test suite reads to pick a test instead of showing its menu
+const TEST_SELECTOR: usize = 0x1ff;
+
+const IBM_LOGO: u8 = 1;
+const CORAX: u8 = 2;
+const FLAGS: u8 = 3;
+const QUIRKS: u8 = 4;
+const KEYPAD: u8 = 5;
+
+/// Most tests halt well before this, the interactive ones end up waiting for a key
+const MAX_FRAMES: usize = 600;
+
+/// Large enough that frames only end on a draw with the display wait quirk
+const INSTRUCTIONS_PER_FRAME: usize = 1000;
+
+fn key(key: u8) -> u16 {
+    1 << key
+}
+
+fn rom(name: &str) -> Vec<u8> {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "roms", name]
+        .iter()
+        .collect();
+    fs::read(&path).unwrap_or_else(|e| panic!("can't read {}: {}", path.display(), e))
+}
+
+/// Runs until the ROM halts on a jump to itself or for `MAX_FRAMES`. A non-zero
+/// `selector` picks a test of the suite. `keys` holds the keypad bitmask to set
+/// from a given frame on.
+fn run(rom: &[u8], quirks: Quirks, selector: u8, keys: &[(usize, u16)]) -> Framebuffer {
+    let mut interpreter = Interpreter::new(rom.to_vec(), quirks).unwrap();
+    if selector != 0 {
+        interpreter.memory[TEST_SELECTOR] = selector;
+    }
+    for frame in 0..MAX_FRAMES {
+        if let Some(&(_, pressed)) = keys.iter().find(|&&(at, _)| at == frame) {
+            interpreter.set_keys(pressed);
+        }
+        interpreter.run_frame(INSTRUCTIONS_PER_FRAME).unwrap();
+
+        let pc = interpreter.pc();
+        if decode_at(&interpreter.memory, pc as usize) == Jp(pc) {
+            break;
+        }
+    }
+    interpreter.framebuffer().clone()
+}
+
+/// Compares the display on `profile` with the golden `name.pbm`
+fn check_golden(name: &str, profile: &str, framebuffer: &Framebuffer) {
+    let path: PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "golden",
+        &format!("{}.pbm", name),
+    ]
+    .iter()
+    .collect();
+    let actual = framebuffer.to_pbm();
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("can't read {}: {}", path.display(), e));
+    assert!(
+        actual == expected,
+        "{} on {} doesn't match {}, got:\n{}",
+        name,
+        profile,
+        path.display(),
+        actual.replace('0', ".").replace('1', "#")
+    );
+}
+
+fn check_suite_test(name: &str, selector: u8, keys: &[(usize, u16)]) {
+    let rom = rom(TEST_SUITE);
+    for (profile, quirks) in PROFILES {
+        check_golden(name, profile, &run(&rom, quirks(), selector, keys));
+    }
+}
+
+#[test]
+fn ibm_logo() {
+    let rom = rom("ibm-logo.ch8");
+    for (profile, quirks) in PROFILES {
+        check_golden("ibm-logo", profile, &run(&rom, quirks(), 0, &[]));
+    }
+}
+
+#[test]
+fn ibm_logo_from_the_suite() {
+    let rom = rom(TEST_SUITE);
+    for (profile, quirks) in PROFILES {
+        check_golden("ibm-logo", profile, &run(&rom, quirks(), IBM_LOGO, &[]));
+    }
+}
+
+#[test]
+fn corax_opcodes() {
+    check_suite_test("corax", CORAX, &[]);
+}
+
+#[test]
+fn flags() {
+    check_suite_test("flags", FLAGS, &[]);
+}
+
+#[test]
+fn quirks() {
+    // The test asks which platform to check: 1 CHIP-8, 2 SUPER-CHIP, 3 XO-CHIP
+    let rom = rom(TEST_SUITE);
+    for ((profile, quirks), platform) in PROFILES.into_iter().zip([1, 1, 2, 3]) {
+        let keys = [(30, key(platform)), (100, 0)];
+        let golden = format!("quirks-{}", profile);
+        check_golden(&golden, profile, &run(&rom, quirks(), QUIRKS, &keys));
+    }
+}
+
+#[test]
+fn keypad_ex9e() {
+    // Picks the EX9E test, then holds A, which stays lit
+    let keys = [(30, key(1)), (100, 0), (150, key(0xa))];
+    check_suite_test("keypad-ex9e", KEYPAD, &keys);
+}
+
+#[test]
+fn keypad_fx0a() {
+    // Picks the FX0A test, then presses and releases A
+    let keys = [(30, key(3)), (100, 0), (150, key(0xa)), (200, 0)];
+    check_suite_test("keypad-fx0a", KEYPAD, &keys);
+}
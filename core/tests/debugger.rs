@@ -0,0 +1,122 @@
+use chip8_core::{Command, DebugEvent, Debugger, Interpreter, Quirks};
+
+// 200: LD v0 01, 202: ADD v0 01, 204: CALL 20a, 206: JP 202, 20a: LD v1 v0, 20c: RET
+const PROGRAM: [u8; 14] = [
+    0x60, 0x01, 0x70, 0x01, 0x22, 0x0a, 0x12, 0x02, 0x00, 0x00, 0x81, 0x00, 0x00, 0xee,
+];
+
+fn setup() -> (Debugger, Interpreter) {
+    (
+        Debugger::new(),
+        Interpreter::new(PROGRAM.to_vec(), Quirks::default()).unwrap(),
+    )
+}
+
+fn run(debugger: &mut Debugger, interpreter: &mut Interpreter, line: &str) -> String {
+    debugger
+        .execute(line.parse().unwrap(), interpreter)
+        .unwrap()
+}
+
+#[test]
+fn parses_commands() {
+    assert_eq!("b 0x20a".parse(), Ok(Command::Break(Some(0x20a))));
+    assert_eq!("break".parse(), Ok(Command::Break(None)));
+    assert_eq!("s".parse(), Ok(Command::Step(1)));
+    assert_eq!("step 5".parse(), Ok(Command::Step(5)));
+    assert_eq!(
+        "m 200 8".parse(),
+        Ok(Command::Memory {
+            addr: 0x200,
+            length: 8
+        })
+    );
+    assert!("step x".parse::<Command>().is_err());
+    assert!("delete".parse::<Command>().is_err());
+    assert!("jump".parse::<Command>().is_err());
+}
+
+#[test]
+fn starts_paused() {
+    let (mut debugger, mut interpreter) = setup();
+    assert_eq!(
+        debugger.run_frame(&mut interpreter, 10),
+        Ok(DebugEvent::Paused)
+    );
+    assert_eq!(interpreter.pc(), 0x200);
+}
+
+#[test]
+fn stops_at_breakpoints_and_resumes() {
+    let (mut debugger, mut interpreter) = setup();
+    run(&mut debugger, &mut interpreter, "b 20a");
+    run(&mut debugger, &mut interpreter, "c");
+
+    assert_eq!(
+        debugger.run_frame(&mut interpreter, 10),
+        Ok(DebugEvent::Breakpoint(0x20a))
+    );
+    assert!(debugger.is_paused());
+    assert_eq!(interpreter.registers()[0], 2);
+    assert_eq!(interpreter.registers()[1], 0);
+
+    // Continuing runs the instruction under the breakpoint, then stops on the next visit
+    run(&mut debugger, &mut interpreter, "c");
+    assert_eq!(
+        debugger.run_frame(&mut interpreter, 10),
+        Ok(DebugEvent::Breakpoint(0x20a))
+    );
+    assert_eq!(interpreter.registers()[0], 3);
+    assert_eq!(interpreter.registers()[1], 2);
+
+    run(&mut debugger, &mut interpreter, "d 20a");
+    run(&mut debugger, &mut interpreter, "c");
+    assert!(matches!(
+        debugger.run_frame(&mut interpreter, 10),
+        Ok(DebugEvent::Ran(_))
+    ));
+}
+
+#[test]
+fn steps_instructions() {
+    let (mut debugger, mut interpreter) = setup();
+    let output = run(&mut debugger, &mut interpreter, "step 3");
+    assert_eq!(interpreter.pc(), 0x20a);
+    assert!(output.starts_with(">  20a"));
+    assert_eq!(run(&mut debugger, &mut interpreter, "stack"), "206");
+}
+
+#[test]
+fn marks_pc_and_breakpoints_in_disassembly() {
+    let (mut debugger, mut interpreter) = setup();
+    run(&mut debugger, &mut interpreter, "b 204");
+    run(&mut debugger, &mut interpreter, "s");
+    let output = run(&mut debugger, &mut interpreter, "dis 200 3");
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(lines[0].starts_with("   200  6001"));
+    assert!(lines[1].starts_with(">  202  7001"));
+    assert!(lines[2].starts_with(" * 204  220a"));
+}
+
+#[test]
+fn dumps_memory_and_registers() {
+    let (mut debugger, mut interpreter) = setup();
+    assert_eq!(
+        run(&mut debugger, &mut interpreter, "m 200 4"),
+        "200  60 01 70 01"
+    );
+    let dump = run(
+        &mut debugger,
+        &mut interpreter,
+        "mem ff0 18446744073709551615",
+    );
+    assert_eq!(dump.lines().count(), 1);
+    assert!(dump.starts_with("ff0  "));
+    run(&mut debugger, &mut interpreter, "s");
+    let registers = run(&mut debugger, &mut interpreter, "r");
+    assert!(registers.starts_with("pc 202"));
+    assert!(registers.contains("v0 01"));
+
+    interpreter.set_register(0x13, 0x2a);
+    assert_eq!(interpreter.registers()[3], 0x2a);
+}
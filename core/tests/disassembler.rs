@@ -0,0 +1,189 @@
+use chip8_core::{DisasmLine, Disassembler};
+
+#[test]
+fn returns_structured_lines() {
+    // LD v1, 42; JP 2a4; DRW v1 v2 5
+    let lines = Disassembler::default().disassemble(&[0x61, 0x2a, 0x12, 0xa4, 0xd1, 0x25]);
+    assert_eq!(
+        lines[0],
+        DisasmLine {
+            addr: 0x200,
+            bytes: vec![0x61, 0x2a],
+            mnemonic: "LD".to_string(),
+            operands: vec!["v1".to_string(), "42".to_string()],
+            label: None,
+            callers: vec![],
+        }
+    );
+    assert_eq!(lines[1].addr, 0x202);
+    assert_eq!(lines[1].text(), "JP 2a4");
+    assert_eq!(lines[2].text(), "DRW v1, v2, 5");
+}
+
+#[test]
+fn prints_addresses_and_bytes() {
+    let lines = Disassembler::default().disassemble(&[0x00, 0xe0, 0xf0, 0x00, 0x12, 0x34]);
+    let listing: Vec<String> = lines.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        listing,
+        ["200  00e0      CLS", "202  f0001234  LD I, LONG 1234"]
+    );
+}
+
+#[test]
+fn leftover_bytes_are_data() {
+    // 5XY1 isn't an instruction, and the last byte has no partner
+    let lines = Disassembler::default().disassemble(&[0x51, 0x21, 0x61]);
+    assert_eq!(lines[0].text(), "db 0x51, 0x21");
+    assert_eq!(lines[1].addr, 0x202);
+    assert_eq!(lines[1].text(), "db 0x61");
+}
+
+fn listing(program: &[u8]) -> Vec<String> {
+    Disassembler::default()
+        .disassemble(program)
+        .iter()
+        .map(|line| format!("{:03x} {}", line.addr, line.text()))
+        .collect()
+}
+
+#[test]
+fn separates_code_from_sprites() {
+    let program = [
+        0xa2, 0x0d, // LD I, 20d
+        0x60, 0x00, // LD v0, 0
+        0x22, 0x09, // CALL 209
+        0x12, 0x06, // JP 206
+        0xff, // padding
+        0xd0, 0x05, // DRW v0, v0, 5
+        0x00, 0xee, // RET
+        0xf0, 0x90, 0x90, 0x90, 0xf0, // sprite
+        0x61, 0x01, // LD v1, 1, never reached
+    ];
+    assert_eq!(
+        listing(&program),
+        [
+            "200 LD I, sprite_20D",
+            "202 LD v0, 0",
+            "204 CALL sub_209",
+            "206 JP loop_206",
+            "208 db 0xff",
+            "209 DRW v0, v0, 5",
+            "20b RET",
+            "20d db 0xf0, 0x90, 0x90, 0x90, 0xf0",
+            "212 LD v1, 1",
+        ]
+    );
+}
+
+#[test]
+fn follows_both_sides_of_skips() {
+    let program = [
+        0x30, 0x00, // SE v0, 0
+        0x12, 0x08, // JP 208
+        0xf0, 0x00, 0x02, 0x0c, // LD I, LONG 20c
+        0xd0, 0x12, // DRW v0, v1, 2
+        0x12, 0x0a, // JP 20a
+        0xe7, 0xa5, // sprite
+    ];
+    assert_eq!(
+        listing(&program),
+        [
+            "200 SE v0, 0",
+            "202 JP label_208",
+            "204 LD I, LONG data_20C",
+            "208 DRW v0, v1, 2",
+            "20a JP loop_20A",
+            "20c db 0xe7, 0xa5",
+        ]
+    );
+}
+
+#[test]
+fn splits_data_at_each_reference() {
+    let mut program = vec![
+        0xa2, 0x0c, // LD I, 20c
+        0xd0, 0x14, // DRW v0, v1, 4
+        0xa2, 0x10, // LD I, 210
+        0xd0, 0x1a, // DRW v0, v1, 10
+        0x12, 0x08, // JP 208
+        0x00, 0x00, // padding
+    ];
+    program.extend([0x55; 14]);
+    let lines = listing(&program);
+    assert_eq!(lines[5], "20a db 0x00, 0x00");
+    assert_eq!(lines[6], "20c db 0x55, 0x55, 0x55, 0x55");
+    assert_eq!(
+        lines[7],
+        "210 db 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55"
+    );
+    assert_eq!(lines[8], "218 db 0x55, 0x55");
+}
+
+#[test]
+fn disassembles_memory_ranges() {
+    let mut memory = vec![0; 0x1000];
+    memory[0x300..0x304].copy_from_slice(&[0x22, 0x00, 0x00, 0xee]);
+    let lines = Disassembler::default().disassemble_at(&memory, 0x300, 2);
+    let text: Vec<String> = lines.iter().map(DisasmLine::text).collect();
+    assert_eq!(text, ["CALL 200", "RET"]);
+    assert_eq!(
+        Disassembler::default()
+            .disassemble_at(&memory, 0xffe, 4)
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn labels_targets_and_lists_callers() {
+    let program = [
+        0x22, 0x08, // CALL 208
+        0x22, 0x08, // CALL 208
+        0xa2, 0x0c, // LD I, 20c
+        0x12, 0x00, // JP 200
+        0x00, 0xee, // RET
+        0x12, 0x0b, // JP 20b, into the middle of a line
+        0x01, 0x02, // data read by nothing, so only named
+    ];
+    let lines = Disassembler::default().disassemble(&program);
+    let labels: Vec<_> = lines.iter().map(|line| line.label.as_deref()).collect();
+    assert_eq!(
+        labels,
+        [
+            Some("loop_200"),
+            None,
+            None,
+            None,
+            Some("sub_208"),
+            None,
+            Some("data_20C")
+        ]
+    );
+    assert_eq!(lines[4].callers, [0x200, 0x202]);
+    assert_eq!(lines[0].callers, []);
+    assert_eq!(lines[5].text(), "JP 20b");
+}
+
+#[test]
+fn listing_is_assembly_source() {
+    let program = [
+        0x22, 0x04, // CALL 204
+        0x12, 0x02, // JP 202
+        0x00, 0xe0, // CLS
+        0x00, 0xee, // RET
+    ];
+    assert_eq!(
+        Disassembler::default().listing(&program),
+        "    CALL sub_204            ; 200  2204
+
+loop_202:
+    JP loop_202             ; 202  1202
+
+; called from 200
+sub_204:
+    CLS                     ; 204  00e0
+    RET                     ; 206  00ee
+"
+    );
+}
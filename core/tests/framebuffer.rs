@@ -0,0 +1,98 @@
+use chip8_core::constants::DISPLAY_MEM_START;
+use chip8_core::{Framebuffer, Interpreter, Quirks};
+
+#[test]
+fn xor_sprite_reports_collisions() {
+    let mut framebuffer = Framebuffer::new(64, 32);
+    assert!(!framebuffer.xor_sprite(0, 0, &[0b1100_0000], true));
+    assert!(framebuffer.get(0, 0));
+    assert!(framebuffer.get(1, 0));
+    assert!(!framebuffer.get(2, 0));
+
+    assert!(framebuffer.xor_sprite(1, 0, &[0b1000_0000], true));
+    assert!(framebuffer.get(0, 0));
+    assert!(!framebuffer.get(1, 0));
+}
+
+#[test]
+fn xor_sprite_clips_at_the_edges() {
+    let mut framebuffer = Framebuffer::new(64, 32);
+    framebuffer.xor_sprite(60, 31, &[0xff, 0xff], true);
+
+    assert!(framebuffer.get(63, 31));
+    assert!(!framebuffer.get(0, 31));
+    assert!(!framebuffer.get(60, 0));
+}
+
+#[test]
+fn xor_sprite_wraps_around_the_edges() {
+    let mut framebuffer = Framebuffer::new(64, 32);
+    framebuffer.xor_sprite(60, 31, &[0xff, 0xff], false);
+
+    assert!(framebuffer.get(63, 31));
+    assert!(framebuffer.get(3, 31));
+    assert!(framebuffer.get(60, 0));
+    assert!(framebuffer.get(3, 0));
+}
+
+#[test]
+fn starting_position_wraps_even_when_clipping() {
+    let mut framebuffer = Framebuffer::new(64, 32);
+    framebuffer.xor_sprite(64 + 2, 32 + 1, &[0x80], true);
+    assert!(framebuffer.get(2, 1));
+}
+
+#[test]
+fn rows_iterate_top_to_bottom() {
+    let mut framebuffer = Framebuffer::new(64, 32);
+    framebuffer.set(5, 1, true);
+
+    let rows: Vec<&[u8]> = framebuffer.rows().collect();
+    assert_eq!(rows.len(), 32);
+    assert_eq!(rows[1][5], 1);
+    assert_eq!(
+        rows.iter()
+            .flat_map(|row| row.iter())
+            .filter(|&&color| color != 0)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn vip_memory_layout_mirrors_the_display() {
+    // I = font "0", draw it at (0, 0)
+    let program = vec![0xa0, 0x50, 0xd0, 0x05];
+    let quirks = Quirks {
+        vip_memory_layout: true,
+        ..Quirks::vip()
+    };
+    let mut interpreter = Interpreter::new(program, quirks).unwrap();
+    interpreter.tick().unwrap();
+    interpreter.tick().unwrap();
+
+    assert_eq!(interpreter.memory[DISPLAY_MEM_START], 0xf0);
+    assert_eq!(interpreter.memory[DISPLAY_MEM_START + 8], 0x90);
+}
+
+#[test]
+fn display_is_kept_out_of_ram_by_default() {
+    let program = vec![0xa0, 0x50, 0xd0, 0x05];
+    let mut interpreter = Interpreter::new(program, Quirks::default()).unwrap();
+    interpreter.tick().unwrap();
+    interpreter.tick().unwrap();
+
+    assert!(interpreter.framebuffer().get(0, 0));
+    assert!(interpreter.memory[DISPLAY_MEM_START..]
+        .iter()
+        .all(|&b| b == 0));
+}
+
+#[test]
+fn exports_plain_pbm() {
+    let mut framebuffer = Framebuffer::new(4, 2);
+    framebuffer.set(1, 0, true);
+    framebuffer.set(3, 1, true);
+
+    assert_eq!(framebuffer.to_pbm(), "P1\n4 2\n0100\n0001\n");
+}
@@ -0,0 +1,167 @@
+use chip8_core::{DebugEvent, GdbStub, Interpreter, Quirks};
+
+// 200: LD v0 01, 202: ADD v0 01, 204: CALL 20a, 206: JP 202, 20a: LD v1 v0, 20c: RET
+const PROGRAM: [u8; 14] = [
+    0x60, 0x01, 0x70, 0x01, 0x22, 0x0a, 0x12, 0x02, 0x00, 0x00, 0x81, 0x00, 0x00, 0xee,
+];
+
+fn packet(payload: &str) -> Vec<u8> {
+    let checksum = payload
+        .bytes()
+        .fold(0u8, |sum, byte| sum.wrapping_add(byte));
+    format!("${}#{:02x}", payload, checksum).into_bytes()
+}
+
+fn setup() -> (GdbStub, Interpreter) {
+    (
+        GdbStub::new(),
+        Interpreter::new(PROGRAM.to_vec(), Quirks::default()).unwrap(),
+    )
+}
+
+/// Sends a packet and returns the reply payload, checking the ack and checksum
+fn request(stub: &mut GdbStub, interpreter: &mut Interpreter, payload: &str) -> String {
+    stub.receive(&packet(payload), interpreter);
+    let output = String::from_utf8(stub.take_output()).unwrap();
+    let reply = output.strip_prefix('+').expect("packet not acked");
+    assert_eq!(
+        reply.as_bytes(),
+        packet(&reply[1..reply.len() - 3]).as_slice()
+    );
+    reply[1..reply.len() - 3].to_string()
+}
+
+#[test]
+fn reads_and_writes_registers() {
+    let (mut stub, mut interpreter) = setup();
+    assert_eq!(
+        request(&mut stub, &mut interpreter, "g"),
+        format!("{}00000200000000", "00".repeat(16))
+    );
+
+    assert_eq!(request(&mut stub, &mut interpreter, "P3=2a"), "OK");
+    assert_eq!(request(&mut stub, &mut interpreter, "P10=0345"), "OK");
+    assert_eq!(request(&mut stub, &mut interpreter, "p3"), "2a");
+    assert_eq!(request(&mut stub, &mut interpreter, "p10"), "0345");
+    assert_eq!(interpreter.registers()[3], 0x2a);
+    assert_eq!(interpreter.index(), 0x345);
+
+    // Wrong width, unknown register and the call stack depth are refused
+    assert_eq!(request(&mut stub, &mut interpreter, "P3=2a2a"), "E01");
+    assert_eq!(request(&mut stub, &mut interpreter, "p15"), "E01");
+    assert_eq!(request(&mut stub, &mut interpreter, "P12=01"), "E01");
+
+    let registers = format!("{}03000206000a05", "11".repeat(16));
+    assert_eq!(
+        request(&mut stub, &mut interpreter, &format!("G{}", registers)),
+        "OK"
+    );
+    assert_eq!(interpreter.pc(), 0x206);
+    assert_eq!(interpreter.sound_timer(), 5);
+    assert_eq!(request(&mut stub, &mut interpreter, "g"), registers);
+}
+
+#[test]
+fn reads_and_writes_memory() {
+    let (mut stub, mut interpreter) = setup();
+    assert_eq!(request(&mut stub, &mut interpreter, "m200,4"), "60017001");
+    assert_eq!(request(&mut stub, &mut interpreter, "M300,2:abcd"), "OK");
+    assert_eq!(interpreter.memory[0x300..0x302], [0xab, 0xcd]);
+    assert_eq!(request(&mut stub, &mut interpreter, "mfff,2"), "E01");
+    assert_eq!(request(&mut stub, &mut interpreter, "M300,2:ab"), "E01");
+    assert_eq!(
+        request(&mut stub, &mut interpreter, "mffffffffffffffff,2"),
+        "E01"
+    );
+    assert_eq!(
+        request(&mut stub, &mut interpreter, "Mffffffffffffffff,2:abcd"),
+        "E01"
+    );
+}
+
+#[test]
+fn stops_at_breakpoints() {
+    let (mut stub, mut interpreter) = setup();
+    assert_eq!(request(&mut stub, &mut interpreter, "?"), "S05");
+    assert_eq!(request(&mut stub, &mut interpreter, "Z0,20a,2"), "OK");
+    assert_eq!(request(&mut stub, &mut interpreter, "Z0,1020a,2"), "E01");
+    assert_eq!(request(&mut stub, &mut interpreter, "z0,1020a,2"), "E01");
+
+    stub.receive(&packet("c"), &mut interpreter);
+    assert_eq!(stub.take_output(), b"+");
+    assert!(!stub.is_paused());
+
+    assert_eq!(
+        stub.run_frame(&mut interpreter, 10),
+        Ok(DebugEvent::Breakpoint(0x20a))
+    );
+    assert_eq!(stub.take_output(), packet("S05"));
+    assert_eq!(request(&mut stub, &mut interpreter, "p11"), "020a");
+
+    assert_eq!(request(&mut stub, &mut interpreter, "z0,20a,2"), "OK");
+    stub.receive(&packet("vCont;c"), &mut interpreter);
+    stub.take_output();
+    assert!(matches!(
+        stub.run_frame(&mut interpreter, 10),
+        Ok(DebugEvent::Ran(_))
+    ));
+}
+
+#[test]
+fn steps() {
+    let (mut stub, mut interpreter) = setup();
+    assert_eq!(request(&mut stub, &mut interpreter, "s"), "S05");
+    assert_eq!(interpreter.pc(), 0x202);
+    assert_eq!(request(&mut stub, &mut interpreter, "vCont;s:1"), "S05");
+    assert_eq!(interpreter.registers()[0], 2);
+    assert!(stub.is_paused());
+}
+
+#[test]
+fn interrupts_a_running_target() {
+    let (mut stub, mut interpreter) = setup();
+    stub.receive(&packet("c"), &mut interpreter);
+    stub.take_output();
+    stub.receive(&[0x03], &mut interpreter);
+    assert!(stub.is_paused());
+    assert_eq!(stub.take_output(), packet("S02"));
+}
+
+#[test]
+fn handles_the_protocol() {
+    let (mut stub, mut interpreter) = setup();
+    assert!(
+        request(&mut stub, &mut interpreter, "qSupported:multiprocess+")
+            .contains("qXfer:features:read+")
+    );
+    let xml = request(
+        &mut stub,
+        &mut interpreter,
+        "qXfer:features:read:target.xml:0,1000",
+    );
+    assert!(xml.starts_with("l<?xml"));
+    assert_eq!(request(&mut stub, &mut interpreter, "vMustReplyEmpty"), "");
+
+    // Bad checksums are nacked, packets may be split across reads
+    stub.receive(b"$g#00", &mut interpreter);
+    assert_eq!(stub.take_output(), b"-");
+    let bytes = packet("m200,1");
+    stub.receive(&bytes[..3], &mut interpreter);
+    stub.receive(&bytes[3..], &mut interpreter);
+    assert_eq!(
+        stub.take_output(),
+        [b"+".as_slice(), &packet("60")].concat()
+    );
+
+    assert_eq!(
+        request(&mut stub, &mut interpreter, "QStartNoAckMode"),
+        "OK"
+    );
+    stub.receive(&packet("m200,1"), &mut interpreter);
+    assert_eq!(stub.take_output(), packet("60"));
+
+    stub.receive(&packet("D"), &mut interpreter);
+    assert_eq!(stub.take_output(), packet("OK"));
+    assert!(!stub.is_attached());
+    assert!(!stub.is_paused());
+}
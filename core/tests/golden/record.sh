@@ -0,0 +1,98 @@
+#!/bin/sh
+# Records the conformance goldens in this directory with the c8 emulator, patched
+# by c8-golden.patch to run a ROM headless and print its display as a PBM. Every
+# ROM runs like in tests/conformance.rs: up to 600 frames of 1000 instructions,
+# stopping on a jump to itself, with keys set from the given frames on.
+#
+# Usage: core/tests/golden/record.sh [--check]
+#
+# --check compares the recordings with the checked-in goldens instead of writing
+# them. Needs curl, patch and cargo, c8 is built once under $TMPDIR.
+set -eu
+
+C8_VERSION=1.0.1
+C8_SHA256=d64cb09ad7b5445df89cf71a463e83853c98989c6369dd3827335edf7937383e
+PROFILES="vip chip48 schip xochip"
+FRAMES=600
+INSTRUCTIONS_PER_FRAME=1000
+
+golden=$(cd "$(dirname "$0")" && pwd)
+roms=$golden/../roms
+check=false
+[ "${1:-}" = "--check" ] && check=true
+
+work=${TMPDIR:-/tmp}/chip8-golden-c8
+c8=$work/c8-$C8_VERSION/target/release/c8
+if [ ! -x "$c8" ]; then
+    rm -rf "$work"
+    mkdir -p "$work"
+    curl -sSfL "https://crates.io/api/v1/crates/c8/$C8_VERSION/download" -o "$work/c8.crate"
+    echo "$C8_SHA256  $work/c8.crate" | sha256sum -c -
+    tar xzf "$work/c8.crate" -C "$work"
+    patch -d "$work/c8-$C8_VERSION" -p1 < "$golden/c8-golden.patch"
+    cargo build --release --locked --manifest-path "$work/c8-$C8_VERSION/Cargo.toml"
+fi
+
+failed=false
+
+# run <rom> <profile> <selector> [frame:keys ...], keys being a hex bitmask
+run() {
+    rom=$1 profile=$2 selector=$3
+    shift 3
+    C8_GOLDEN="$roms/$rom classic $profile $selector $INSTRUCTIONS_PER_FRAME $FRAMES $*" \
+        "$c8" 2>/dev/null
+}
+
+save() {
+    if $check; then
+        if ! cmp -s "$1" "$golden/$2.pbm"; then
+            echo "$2.pbm doesn't match c8" >&2
+            failed=true
+        fi
+        rm "$1"
+    else
+        cat "$1" > "$golden/$2.pbm"
+        rm "$1"
+    fi
+}
+
+# record <golden> <rom> <selector> [frame:keys ...]: one golden for all profiles,
+# failing if they don't all draw the same
+record() {
+    name=$1 rom=$2
+    shift 2
+    out=$(mktemp)
+    run "$rom" vip "$@" > "$out"
+    for profile in $PROFILES; do
+        if ! run "$rom" "$profile" "$@" | cmp -s - "$out"; then
+            echo "$name differs on $profile, it needs a golden per profile" >&2
+            exit 1
+        fi
+    done
+    save "$out" "$name"
+}
+
+# record_per_profile <golden> <profile> <rom> <selector> [frame:keys ...]
+record_per_profile() {
+    name=$1-$2 profile=$2 rom=$3
+    shift 3
+    out=$(mktemp)
+    run "$rom" "$profile" "$@" > "$out"
+    save "$out" "$name"
+}
+
+record ibm-logo ibm-logo.ch8 0
+record corax chip8-test-suite.ch8 2
+record flags chip8-test-suite.ch8 3
+record keypad-ex9e chip8-test-suite.ch8 5 30:2 100:0 150:400
+record keypad-fx0a chip8-test-suite.ch8 5 30:8 100:0 150:400 200:0
+
+# The quirks test asks for the platform: key 1 CHIP-8, 2 SUPER-CHIP, 3 XO-CHIP
+record_per_profile quirks vip chip8-test-suite.ch8 4 30:2 100:0
+record_per_profile quirks chip48 chip8-test-suite.ch8 4 30:2 100:0
+record_per_profile quirks schip chip8-test-suite.ch8 4 30:4 100:0
+record_per_profile quirks xochip chip8-test-suite.ch8 4 30:8 100:0
+
+if $failed; then
+    exit 1
+fi
@@ -0,0 +1,56 @@
+use chip8_core::{ExecutionState, Interpreter, Quirks};
+
+/// V0 = 5, DT = V0, V1 = K, then copy DT into V2
+const WAIT_FOR_KEY: [u8; 8] = [0x60, 0x05, 0xf0, 0x15, 0xf1, 0x0a, 0xf2, 0x07];
+
+#[test]
+fn fx0a_blocks_until_a_key_is_released() {
+    let mut interpreter = Interpreter::new(WAIT_FOR_KEY.to_vec(), Quirks::default()).unwrap();
+
+    let result = interpreter.run_frame(10).unwrap();
+    assert_eq!(result.wait_for_keyboard, Some(1));
+
+    interpreter.press_key(0xa, true);
+    interpreter.run_frame(10).unwrap();
+    assert!(matches!(
+        interpreter.state(),
+        ExecutionState::WaitingForKey {
+            register: 1,
+            key: Some(0xa)
+        }
+    ));
+
+    interpreter.press_key(0xa, false);
+    assert_eq!(interpreter.state(), ExecutionState::Running);
+    assert_eq!(interpreter.registers()[1], 0xa);
+
+    // The delay timer kept counting down while execution was blocked
+    interpreter.tick().unwrap();
+    assert_eq!(interpreter.registers()[2], 3);
+}
+
+#[test]
+fn fx0a_ignores_keys_held_before_the_wait() {
+    let mut interpreter = Interpreter::new(WAIT_FOR_KEY.to_vec(), Quirks::default()).unwrap();
+    interpreter.press_key(0x3, true);
+    interpreter.run_frame(10).unwrap();
+
+    interpreter.press_key(0x3, false);
+    assert!(matches!(
+        interpreter.state(),
+        ExecutionState::WaitingForKey { .. }
+    ));
+}
+
+#[test]
+fn ex9e_uses_the_low_nibble_of_vx() {
+    // V0 = 0x25, SKP V0, V1 = 1, V2 = 2
+    let program = vec![0x60, 0x25, 0xe0, 0x9e, 0x61, 0x01, 0x62, 0x02];
+    let mut interpreter = Interpreter::new(program, Quirks::default()).unwrap();
+    interpreter.press_key(0x5, true);
+    for _ in 0..3 {
+        interpreter.tick().unwrap();
+    }
+    assert_eq!(interpreter.registers()[1], 0);
+    assert_eq!(interpreter.registers()[2], 2);
+}
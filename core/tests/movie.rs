@@ -0,0 +1,123 @@
+use chip8_core::{LoadError, Movie, MovieError, Quirks};
+
+// Adds a random number to V1 whenever key 5 is held:
+// V0 = 5, RND v2 ff, SKNP v0, ADD v1 v2, JP 202
+const PROGRAM: [u8; 10] = [0x60, 0x05, 0xc2, 0xff, 0xe0, 0xa1, 0x81, 0x24, 0x12, 0x02];
+
+fn recorded() -> Movie {
+    let mut movie = Movie::new(&PROGRAM, Quirks::schip(), 7, 6);
+    let mut interpreter = movie.interpreter(PROGRAM.to_vec()).unwrap();
+    for frame in 0..20 {
+        let keys = if frame % 3 == 0 { 1 << 5 } else { 0 };
+        movie.record_frame(&mut interpreter, keys, true).unwrap();
+    }
+    movie
+}
+
+#[test]
+fn round_trips_through_text() {
+    let movie = recorded();
+    let text = movie.to_string();
+
+    assert!(text.starts_with("chip8-movie 2\n"));
+    assert_eq!(text.parse::<Movie>(), Ok(movie));
+}
+
+#[test]
+fn replays_the_recording_exactly() {
+    let movie = recorded();
+    let mut recording = movie.interpreter(PROGRAM.to_vec()).unwrap();
+    for frame in &movie.frames {
+        recording.set_keys(frame.keys);
+        recording.run_frame(6).unwrap();
+    }
+
+    let mut playback = movie.interpreter(PROGRAM.to_vec()).unwrap();
+    for frame in 0..movie.len() {
+        movie.play_frame(&mut playback, frame).unwrap();
+    }
+    assert_eq!(playback.save_state(), recording.save_state());
+    assert_ne!(playback.registers()[1], 0);
+
+    assert_eq!(
+        movie.play_frame(&mut playback, movie.len()),
+        Err(MovieError::EndOfMovie {
+            frame: 20,
+            length: 20
+        })
+    );
+    assert_eq!(playback.save_state(), recording.save_state());
+}
+
+#[test]
+fn detects_desyncs() {
+    let mut movie = recorded();
+    movie.frames[4].keys ^= 1 << 5;
+
+    let mut playback = movie.interpreter(PROGRAM.to_vec()).unwrap();
+    let error = (0..movie.len())
+        .map(|frame| movie.play_frame(&mut playback, frame))
+        .find_map(Result::err);
+    assert!(matches!(error, Some(MovieError::Desync { frame: 4, .. })));
+}
+
+#[test]
+fn plays_movies_without_hashes() {
+    let mut movie = recorded();
+    for frame in movie.frames.iter_mut() {
+        frame.state_hash = None;
+    }
+    let text = movie.to_string();
+    assert!(text.lines().last().unwrap().len() == 4);
+
+    let movie: Movie = text.parse().unwrap();
+    let mut playback = movie.interpreter(PROGRAM.to_vec()).unwrap();
+    for frame in 0..movie.len() {
+        movie.play_frame(&mut playback, frame).unwrap();
+    }
+}
+
+#[test]
+fn refuses_other_roms() {
+    let movie = recorded();
+    assert!(matches!(
+        movie.interpreter(vec![0x12, 0x00]),
+        Err(MovieError::RomMismatch { .. })
+    ));
+}
+
+#[test]
+fn reports_invalid_lines() {
+    let text = recorded().to_string().replacen("ipf 6", "ipf six", 1);
+    assert_eq!(text.parse::<Movie>(), Err(MovieError::Invalid { line: 5 }));
+
+    let text = recorded().to_string() + "zz\n";
+    assert_eq!(text.parse::<Movie>(), Err(MovieError::Invalid { line: 27 }));
+
+    assert_eq!("".parse::<Movie>(), Err(MovieError::Invalid { line: 1 }));
+    let text: String = recorded()
+        .to_string()
+        .lines()
+        .take(3)
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert_eq!(text.parse::<Movie>(), Err(MovieError::Invalid { line: 4 }));
+}
+
+#[test]
+fn refuses_unsupported_memory_sizes() {
+    let mut movie = recorded();
+    let text = movie.to_string();
+    let quirks = text.lines().nth(3).unwrap();
+    let huge = format!("{}ffffffff", &quirks[..quirks.len() - 8]);
+    assert_eq!(
+        text.replacen(quirks, &huge, 1).parse::<Movie>(),
+        Err(MovieError::Invalid { line: 4 })
+    );
+
+    movie.quirks.memory_size = 0x100;
+    assert_eq!(
+        movie.interpreter(PROGRAM.to_vec()).err(),
+        Some(MovieError::Load(LoadError::UnsupportedMemorySize(0x100)))
+    );
+}
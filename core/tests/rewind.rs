@@ -0,0 +1,83 @@
+use chip8_core::{Interpreter, Quirks, Rewind};
+
+// I = font "0", DRW v0 v1 5, ADD v1 1, JP 202
+const PROGRAM: [u8; 8] = [0xa0, 0x50, 0xd0, 0x15, 0x71, 0x01, 0x12, 0x02];
+
+fn record(interpreter: &mut Interpreter, rewind: &mut Rewind, frames: usize) -> Vec<Vec<u8>> {
+    (0..frames)
+        .map(|_| {
+            interpreter.run_frame(4).unwrap();
+            let state = interpreter.save_state();
+            rewind.record(state.clone());
+            state
+        })
+        .collect()
+}
+
+#[test]
+fn steps_back_through_every_frame() {
+    let mut interpreter = Interpreter::new(PROGRAM.to_vec(), Quirks::default()).unwrap();
+    let mut rewind = Rewind::new(100);
+    let states = record(&mut interpreter, &mut rewind, 10);
+
+    assert_eq!(rewind.len(), 9);
+    for state in states.iter().rev().skip(1) {
+        assert_eq!(rewind.step_back(), Some(state.as_slice()));
+    }
+    assert_eq!(rewind.step_back(), None);
+}
+
+#[test]
+fn forgets_the_oldest_frames() {
+    let mut interpreter = Interpreter::new(PROGRAM.to_vec(), Quirks::default()).unwrap();
+    let mut rewind = Rewind::new(3);
+    let states = record(&mut interpreter, &mut rewind, 10);
+
+    assert_eq!(rewind.len(), 3);
+    for state in states[6..9].iter().rev() {
+        assert_eq!(rewind.step_back(), Some(state.as_slice()));
+    }
+    assert!(rewind.is_empty());
+}
+
+#[test]
+fn resumes_recording_after_rewinding() {
+    let mut interpreter = Interpreter::new(PROGRAM.to_vec(), Quirks::default()).unwrap();
+    let mut rewind = Rewind::new(100);
+    let states = record(&mut interpreter, &mut rewind, 5);
+
+    interpreter.load_state(rewind.step_back().unwrap()).unwrap();
+    interpreter.load_state(rewind.step_back().unwrap()).unwrap();
+    assert_eq!(interpreter.save_state(), states[2]);
+
+    let replayed = record(&mut interpreter, &mut rewind, 2);
+    assert_eq!(replayed, states[3..5]);
+    assert_eq!(rewind.step_back(), Some(states[3].as_slice()));
+}
+
+#[test]
+fn handles_resolution_changes() {
+    // HIGH, LOW, JP 200
+    let program = vec![0x00, 0xff, 0x00, 0xfe, 0x12, 0x00];
+    let mut interpreter = Interpreter::new(program, Quirks::schip()).unwrap();
+    let mut rewind = Rewind::new(10);
+    let mut states = Vec::new();
+    for _ in 0..3 {
+        interpreter.tick().unwrap();
+        states.push(interpreter.save_state());
+        rewind.record(interpreter.save_state());
+    }
+
+    assert_ne!(states[0].len(), states[1].len());
+    assert_eq!(rewind.step_back(), Some(states[1].as_slice()));
+    assert_eq!(rewind.step_back(), Some(states[0].as_slice()));
+}
+
+#[test]
+fn stores_small_deltas() {
+    let mut interpreter = Interpreter::new(PROGRAM.to_vec(), Quirks::default()).unwrap();
+    let mut rewind = Rewind::new(100);
+    let states = record(&mut interpreter, &mut rewind, 50);
+
+    assert!(rewind.memory_usage() < states[0].len() * 2);
+}
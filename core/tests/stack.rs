@@ -0,0 +1,92 @@
+use chip8_core::constants::{DISPLAY_MEM_START, PC_DEFAULT_START, STACK_START};
+use chip8_core::{ExecError, Interpreter, Quirks};
+
+/// A subroutine at 0x200 that calls itself forever
+const RECURSE: [u8; 2] = [0x22, 0x00];
+
+#[test]
+fn call_and_return() {
+    // CALL 206, JP 204, LD V0 1, RET
+    let program = vec![0x22, 0x06, 0x12, 0x04, 0x00, 0x00, 0x60, 0x01, 0x00, 0xee];
+    let mut interpreter = Interpreter::new(program, Quirks::default()).unwrap();
+
+    interpreter.tick().unwrap();
+    assert_eq!(interpreter.pc(), 0x206);
+    assert_eq!(interpreter.stack().frames(), &[0x202]);
+
+    interpreter.tick().unwrap();
+    interpreter.tick().unwrap();
+    assert_eq!(interpreter.pc(), 0x202);
+    assert!(interpreter.stack().is_empty());
+    assert_eq!(interpreter.registers()[0], 1);
+}
+
+#[test]
+fn recursion_up_to_the_vip_depth() {
+    let mut interpreter = Interpreter::new(RECURSE.to_vec(), Quirks::vip()).unwrap();
+    for _ in 0..12 {
+        interpreter.tick().unwrap();
+    }
+    assert_eq!(interpreter.stack().len(), 12);
+    assert_eq!(interpreter.tick().unwrap_err(), ExecError::StackOverflow);
+}
+
+#[test]
+fn recursion_up_to_the_schip_depth() {
+    let mut interpreter = Interpreter::new(RECURSE.to_vec(), Quirks::schip()).unwrap();
+    for _ in 0..16 {
+        interpreter.tick().unwrap();
+    }
+    assert_eq!(interpreter.tick().unwrap_err(), ExecError::StackOverflow);
+}
+
+#[test]
+fn return_without_call_underflows() {
+    let mut interpreter = Interpreter::new(vec![0x00, 0xee], Quirks::default()).unwrap();
+    assert_eq!(interpreter.tick().unwrap_err(), ExecError::StackUnderflow);
+}
+
+#[test]
+fn calls_do_not_touch_program_memory() {
+    let mut interpreter = Interpreter::new(RECURSE.to_vec(), Quirks::default()).unwrap();
+    let before = interpreter.memory.clone();
+    interpreter.tick().unwrap();
+    assert_eq!(interpreter.memory[..], before[..]);
+}
+
+#[test]
+fn vip_memory_layout_stores_the_stack_at_0xea0() {
+    let quirks = Quirks {
+        vip_memory_layout: true,
+        ..Quirks::vip()
+    };
+    let mut interpreter = Interpreter::new(RECURSE.to_vec(), quirks).unwrap();
+    interpreter.tick().unwrap();
+    interpreter.tick().unwrap();
+
+    let return_addr = (PC_DEFAULT_START + 2) as u16;
+    let expected = return_addr.to_be_bytes();
+    assert_eq!(interpreter.memory[STACK_START..STACK_START + 2], expected);
+    assert_eq!(
+        interpreter.memory[STACK_START + 2..STACK_START + 4],
+        expected
+    );
+}
+
+#[test]
+fn vip_stack_stops_before_the_display() {
+    let mut quirks = Quirks::vip();
+    quirks.vip_memory_layout = true;
+    quirks.stack_depth = 200;
+    let mut interpreter = Interpreter::new(RECURSE.to_vec(), quirks).unwrap();
+
+    // 0xea0 to 0xf00 holds 48 return addresses
+    for _ in 0..48 {
+        interpreter.tick().unwrap();
+    }
+    assert_eq!(interpreter.tick().unwrap_err(), ExecError::StackOverflow);
+    assert_eq!(interpreter.stack().len(), 48);
+    assert!(interpreter.memory[DISPLAY_MEM_START..]
+        .iter()
+        .all(|&byte| byte == 0));
+}
@@ -0,0 +1,143 @@
+use chip8_core::state::{rom_hash, MAGIC, VERSION};
+use chip8_core::{ExecutionState, Interpreter, LoadError, Quirks, StateError};
+
+// V0 = 5, LD ST v0, CALL 208, JP 206, I = font "0", DRW v0 v0 5, ADD v0 1, RET
+const PROGRAM: [u8; 16] = [
+    0x60, 0x05, 0xf0, 0x18, 0x22, 0x08, 0x12, 0x06, 0xa0, 0x50, 0xd0, 0x05, 0x70, 0x01, 0x00, 0xee,
+];
+
+fn running() -> Interpreter {
+    let mut interpreter = Interpreter::new(PROGRAM.to_vec(), Quirks::schip()).unwrap();
+    for _ in 0..5 {
+        interpreter.tick().unwrap();
+    }
+    interpreter.tick_timers();
+    interpreter.press_key(3, true);
+    interpreter
+}
+
+#[test]
+fn header_identifies_the_rom() {
+    let state = running().save_state();
+    assert_eq!(&state[..4], MAGIC);
+    assert_eq!(state[4..6], VERSION.to_le_bytes());
+    assert_eq!(state[6..14], rom_hash(&PROGRAM).to_le_bytes());
+}
+
+#[test]
+fn round_trips_the_machine() {
+    let original = running();
+    let state = original.save_state();
+
+    let mut restored = Interpreter::new(PROGRAM.to_vec(), Quirks::default()).unwrap();
+    restored.load_state(&state).unwrap();
+
+    assert_eq!(restored.save_state(), state);
+    assert_eq!(restored.quirks(), &Quirks::schip());
+    assert_eq!(restored.registers(), original.registers());
+    assert_eq!(restored.pc(), original.pc());
+    assert_eq!(restored.stack(), original.stack());
+    assert_eq!(restored.sound_timer(), 4);
+    assert_eq!(restored.framebuffer(), original.framebuffer());
+    assert_eq!(restored.memory, original.memory);
+}
+
+#[test]
+fn restored_machine_keeps_running() {
+    let mut original = running();
+    let mut restored = Interpreter::new(PROGRAM.to_vec(), Quirks::schip()).unwrap();
+    restored.load_state(&original.save_state()).unwrap();
+
+    for _ in 0..3 {
+        original.tick().unwrap();
+        restored.tick().unwrap();
+    }
+    assert_eq!(restored.save_state(), original.save_state());
+}
+
+#[test]
+fn keeps_waiting_for_a_key() {
+    // LD v1 K
+    let mut interpreter = Interpreter::new(vec![0xf1, 0x0a], Quirks::default()).unwrap();
+    interpreter.tick().unwrap();
+    interpreter.press_key(7, true);
+    let state = interpreter.save_state();
+
+    let mut restored = Interpreter::new(vec![0xf1, 0x0a], Quirks::default()).unwrap();
+    restored.load_state(&state).unwrap();
+    assert_eq!(
+        restored.state(),
+        ExecutionState::WaitingForKey {
+            register: 1,
+            key: Some(7)
+        }
+    );
+}
+
+#[test]
+fn refuses_states_from_other_roms() {
+    let state = running().save_state();
+    let mut other = Interpreter::new(vec![0x12, 0x00], Quirks::schip()).unwrap();
+    let before = other.save_state();
+
+    assert_eq!(
+        other.load_state(&state),
+        Err(StateError::RomMismatch {
+            expected: rom_hash(&[0x12, 0x00]),
+            found: rom_hash(&PROGRAM),
+        })
+    );
+    assert_eq!(other.save_state(), before);
+}
+
+#[test]
+fn refuses_invalid_data() {
+    let state = running().save_state();
+    let mut interpreter = Interpreter::new(PROGRAM.to_vec(), Quirks::schip()).unwrap();
+
+    assert_eq!(
+        interpreter.load_state(b"not a state"),
+        Err(StateError::NotASaveState)
+    );
+
+    let mut future = state.clone();
+    future[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+    assert_eq!(
+        interpreter.load_state(&future),
+        Err(StateError::UnsupportedVersion(VERSION + 1))
+    );
+
+    assert_eq!(
+        interpreter.load_state(&state[..state.len() - 1]),
+        Err(StateError::Corrupted)
+    );
+
+    let mut small = state.clone();
+    small[18..22].copy_from_slice(&16u32.to_le_bytes());
+    assert_eq!(interpreter.load_state(&small), Err(StateError::Corrupted));
+}
+
+#[test]
+fn keeps_custom_stack_depths() {
+    let mut quirks = Quirks::schip();
+    quirks.stack_depth = 300;
+    // CALL 200 forever
+    let mut original = Interpreter::new(vec![0x22, 0x00], quirks).unwrap();
+    for _ in 0..260 {
+        original.tick().unwrap();
+    }
+
+    let mut restored = Interpreter::new(vec![0x22, 0x00], Quirks::default()).unwrap();
+    restored.load_state(&original.save_state()).unwrap();
+    assert_eq!(restored.quirks().stack_depth, 300);
+    assert_eq!(restored.stack(), original.stack());
+
+    quirks.stack_depth = 70000;
+    assert_eq!(
+        Interpreter::new(vec![0x22, 0x00], quirks).err(),
+        Some(LoadError::StackTooDeep {
+            depth: 70000,
+            max: 0xffff
+        })
+    );
+}
@@ -0,0 +1,112 @@
+use chip8_core::constants::BIG_FONT;
+use chip8_core::{ExecError, ExecutionState, Interpreter, Quirks};
+
+fn run(program: &[u8], ticks: usize) -> Interpreter {
+    let mut interpreter = Interpreter::new(program.to_vec(), Quirks::schip()).unwrap();
+    for _ in 0..ticks {
+        interpreter.tick().unwrap();
+    }
+    interpreter
+}
+
+#[test]
+fn switches_resolution() {
+    let interpreter = run(&[0x00, 0xff], 1);
+    assert_eq!(interpreter.framebuffer().width(), 128);
+    assert_eq!(interpreter.framebuffer().height(), 64);
+
+    let interpreter = run(&[0x00, 0xff, 0x00, 0xfe], 2);
+    assert_eq!(interpreter.framebuffer().width(), 64);
+    assert_eq!(interpreter.framebuffer().height(), 32);
+}
+
+#[test]
+fn draws_16x16_sprites() {
+    // HIGH, I = 208, DRW v0 v0 0, then the sprite: a 16 pixel wide bar on each row
+    let mut program = vec![0x00, 0xff, 0xa2, 0x08, 0xd0, 0x00, 0x12, 0x06];
+    program.extend([0xff; 32]);
+    let interpreter = run(&program, 3);
+
+    let framebuffer = interpreter.framebuffer();
+    assert!(framebuffer.get(15, 15));
+    assert!(!framebuffer.get(16, 0));
+    assert!(!framebuffer.get(0, 16));
+    assert_eq!(interpreter.registers()[0xF], 0);
+}
+
+#[test]
+fn counts_collided_and_clipped_rows_in_hires() {
+    // HIGH, V1 = 60, I = 20c, DRW v0 v1 0 twice, then the sprite
+    let mut program = vec![
+        0x00, 0xff, 0x61, 0x3c, 0xa2, 0x0c, 0xd0, 0x10, 0xd0, 0x10, 0x12, 0x0a,
+    ];
+    program.extend([0xff; 32]);
+    let interpreter = run(&program, 5);
+
+    // 4 rows are on screen and collide, the other 12 are clipped
+    assert_eq!(interpreter.registers()[0xF], 16);
+}
+
+#[test]
+fn scrolls_the_display() {
+    // I = font "0", DRW v0 v0 1 draws 1111 at the top left, then scroll
+    let draw = [0xa0, 0x50, 0xd0, 0x01];
+
+    let interpreter = run(&[&draw[..], &[0x00, 0xc2]].concat(), 3);
+    assert!(interpreter.framebuffer().get(0, 2));
+    assert!(!interpreter.framebuffer().get(0, 0));
+
+    let interpreter = run(&[&draw[..], &[0x00, 0xfb]].concat(), 3);
+    assert!(interpreter.framebuffer().get(4, 0));
+    assert!(!interpreter.framebuffer().get(0, 0));
+
+    let interpreter = run(&[&draw[..], &[0x00, 0xfc]].concat(), 3);
+    assert!(!interpreter.framebuffer().get(0, 0));
+    assert!(!interpreter.framebuffer().get(3, 0));
+}
+
+#[test]
+fn exit_stops_execution() {
+    let interpreter = run(&[0x00, 0xfd, 0x60, 0x01], 2);
+    assert_eq!(interpreter.state(), ExecutionState::Exited);
+    assert_eq!(interpreter.registers()[0], 0);
+}
+
+#[test]
+fn points_i_at_the_big_font() {
+    // V0 = 7, LD HF v0, LD v0 [I]
+    let interpreter = run(&[0x60, 0x07, 0xf0, 0x30, 0xf0, 0x65], 3);
+    assert_eq!(interpreter.registers()[0], BIG_FONT[70]);
+}
+
+#[test]
+fn saves_and_restores_rpl_flags() {
+    // V0 = 1, V1 = 2, LD R v1, V0 = 0, V1 = 0, LD v1 R
+    let program = [
+        0x60, 0x01, 0x61, 0x02, 0xf1, 0x75, 0x60, 0x00, 0x61, 0x00, 0xf1, 0x85,
+    ];
+    let interpreter = run(&program, 6);
+    assert_eq!(interpreter.registers()[..2], [1, 2]);
+}
+
+#[test]
+fn vip_has_no_superchip_instructions() {
+    let mut interpreter = Interpreter::new(vec![0x00, 0xff], Quirks::vip()).unwrap();
+    assert_eq!(
+        interpreter.tick(),
+        Err(ExecError::UnknownOpcode {
+            pc: 0x200,
+            opcode: 0x00ff
+        })
+    );
+    assert_eq!(interpreter.framebuffer().width(), 64);
+    assert_eq!(interpreter.framebuffer().height(), 32);
+
+    // I = 206, DRW v0 v0 0 draws nothing, then 32 bytes of sprite
+    let mut program = vec![0xa2, 0x06, 0xd0, 0x00, 0x12, 0x04];
+    program.extend([0xff; 32]);
+    let mut interpreter = Interpreter::new(program, Quirks::vip()).unwrap();
+    interpreter.tick().unwrap();
+    interpreter.tick().unwrap();
+    assert!(!interpreter.framebuffer().get(0, 0));
+}
@@ -0,0 +1,29 @@
+use chip8_core::{Interpreter, Quirks};
+
+#[test]
+fn timers_tick_once_per_frame() {
+    // V0 = 5, DT = V0, then loop forever copying DT into V1
+    let program = vec![0x60, 0x05, 0xf0, 0x15, 0xf1, 0x07, 0x12, 0x04];
+    let mut interpreter = Interpreter::new(program, Quirks::default()).unwrap();
+
+    interpreter.run_frame(100).unwrap();
+    assert_eq!(interpreter.registers()[1], 5);
+
+    interpreter.run_frame(100).unwrap();
+    assert_eq!(interpreter.registers()[1], 4);
+}
+
+#[test]
+fn display_wait_ends_the_frame_after_drawing() {
+    // Draw, then V0 = 1
+    let program = vec![0xd0, 0x01, 0x60, 0x01];
+
+    let mut interpreter = Interpreter::new(program.clone(), Quirks::vip()).unwrap();
+    let result = interpreter.run_frame(10).unwrap();
+    assert!(result.refresh_display);
+    assert_eq!(interpreter.registers()[0], 0);
+
+    let mut interpreter = Interpreter::new(program, Quirks::chip48()).unwrap();
+    interpreter.run_frame(2).unwrap();
+    assert_eq!(interpreter.registers()[0], 1);
+}
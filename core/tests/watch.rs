@@ -0,0 +1,167 @@
+use chip8_core::constants::FONT_START;
+use chip8_core::{
+    Access, Command, DebugEvent, Debugger, GdbStub, Interpreter, Quirks, WatchHit, Watchpoint,
+};
+
+const PROGRAM: [u8; 24] = [
+    0xa3, 0x00, // 200: LD I, 300
+    0x60, 0x2a, // 202: LD v0, 2a
+    0xf0, 0x55, // 204: LD [I], v0
+    0xa3, 0x00, // 206: LD I, 300
+    0xf0, 0x65, // 208: LD v0, [I]
+    0x61, 0x00, // 20a: LD v1, 00
+    0xa3, 0x00, // 20c: LD I, 300
+    0xd1, 0x11, // 20e: DRW v1, v1, 1
+    0x60, 0xff, // 210: LD v0, ff
+    0xa3, 0x00, // 212: LD I, 300
+    0xf0, 0x33, // 214: LD B, v0
+    0x12, 0x16, // 216: JP 216
+];
+
+fn run(watchpoint: Watchpoint, ticks: usize) -> Vec<WatchHit> {
+    let mut interpreter = Interpreter::new(PROGRAM.to_vec(), Quirks::default()).unwrap();
+    interpreter.add_watchpoint(watchpoint);
+    for _ in 0..ticks {
+        interpreter.tick().unwrap();
+    }
+    interpreter.take_watch_hits()
+}
+
+#[test]
+fn reports_writes() {
+    let hits = run(Watchpoint::new(0x300, 1, Access::Write), 12);
+    assert_eq!(
+        hits,
+        [
+            WatchHit {
+                pc: 0x204,
+                addr: 0x300,
+                access: Access::Write,
+                old: 0x00,
+                new: 0x2a
+            },
+            WatchHit {
+                pc: 0x214,
+                addr: 0x300,
+                access: Access::Write,
+                old: 0x2a,
+                new: 0x02
+            },
+        ]
+    );
+}
+
+#[test]
+fn reports_reads_from_loads_and_sprites() {
+    let hits = run(Watchpoint::new(0x300, 1, Access::Read), 12);
+    let pcs: Vec<u16> = hits.iter().map(|hit| hit.pc).collect();
+    assert_eq!(pcs, [0x208, 0x20e]);
+    assert!(hits.iter().all(|hit| hit.old == 0x2a && hit.new == 0x2a));
+}
+
+#[test]
+fn watches_ranges_and_instruction_fetch() {
+    let hits = run(Watchpoint::new(0x301, 2, Access::ReadWrite), 12);
+    let addrs: Vec<usize> = hits.iter().map(|hit| hit.addr).collect();
+    assert_eq!(addrs, [0x302, 0x301]);
+
+    // Fetching 210 and 211
+    let hits = run(Watchpoint::new(0x210, 2, Access::Read), 9);
+    assert_eq!(hits.len(), 2);
+    assert!(hits.iter().all(|hit| hit.pc == 0x210));
+}
+
+#[test]
+fn removes_watchpoints() {
+    let mut interpreter = Interpreter::new(PROGRAM.to_vec(), Quirks::default()).unwrap();
+    interpreter.add_watchpoint(Watchpoint::new(0x300, 1, Access::Write));
+    assert!(interpreter.remove_watchpoint(0x300));
+    assert!(!interpreter.remove_watchpoint(0x300));
+    for _ in 0..3 {
+        interpreter.tick().unwrap();
+    }
+    assert!(interpreter.take_watch_hits().is_empty());
+}
+
+#[test]
+fn clamps_watchpoints_to_memory() {
+    let mut interpreter = Interpreter::new(PROGRAM.to_vec(), Quirks::default()).unwrap();
+    let watchpoint = Watchpoint::new(0x300, usize::MAX, Access::Read);
+    assert_eq!(
+        interpreter.add_watchpoint(watchpoint),
+        Some(Watchpoint::new(0x300, 0xd00, Access::Read))
+    );
+    assert_eq!(
+        interpreter.add_watchpoint(Watchpoint::new(0x1000, 1, Access::Read)),
+        None
+    );
+    assert_eq!(
+        interpreter.add_watchpoint(Watchpoint::new(usize::MAX, 2, Access::Read)),
+        None
+    );
+    for _ in 0..12 {
+        interpreter.tick().unwrap();
+    }
+    assert!(!interpreter.take_watch_hits().is_empty());
+}
+
+#[test]
+fn debugger_stops_after_the_access() {
+    let mut debugger = Debugger::new();
+    // LD I, font; LD [I], v0; JP 204
+    let program = vec![
+        0xa0 | (FONT_START >> 8) as u8,
+        FONT_START as u8,
+        0xf0,
+        0x55,
+        0x12,
+        0x04,
+    ];
+    let mut interpreter = Interpreter::new(program, Quirks::default()).unwrap();
+    let command = format!("watch {:x} 80", FONT_START).parse().unwrap();
+    assert_eq!(
+        command,
+        Command::Watch(Some(Watchpoint::new(FONT_START, 80, Access::Write)))
+    );
+    debugger.execute(command, &mut interpreter).unwrap();
+    debugger.resume();
+
+    match debugger.run_frame(&mut interpreter, 10) {
+        Ok(DebugEvent::Watchpoint(hit)) => {
+            assert_eq!(hit.pc, 0x202);
+            assert_eq!(hit.addr, FONT_START);
+            assert_eq!(hit.old, 0xf0);
+            assert_eq!(hit.new, 0x00);
+        }
+        event => panic!("expected a watchpoint, got {:?}", event),
+    }
+    assert!(debugger.is_paused());
+    assert_eq!(interpreter.pc(), 0x204);
+}
+
+#[test]
+fn gdb_reports_watchpoints() {
+    let mut stub = GdbStub::new();
+    let mut interpreter = Interpreter::new(PROGRAM.to_vec(), Quirks::default()).unwrap();
+    let packet = |payload: &str| {
+        let checksum = payload
+            .bytes()
+            .fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        format!("${}#{:02x}", payload, checksum).into_bytes()
+    };
+
+    stub.receive(&packet("Z2,300,1"), &mut interpreter);
+    assert_eq!(
+        stub.take_output(),
+        [b"+".as_slice(), &packet("OK")].concat()
+    );
+    stub.receive(&packet("Z2,ffffffffffffffff,2"), &mut interpreter);
+    assert_eq!(
+        stub.take_output(),
+        [b"+".as_slice(), &packet("E01")].concat()
+    );
+    stub.receive(&packet("c"), &mut interpreter);
+    stub.take_output();
+    stub.run_frame(&mut interpreter, 10).unwrap();
+    assert_eq!(stub.take_output(), packet("T05watch:300;"));
+}
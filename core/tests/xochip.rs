@@ -0,0 +1,117 @@
+use chip8_core::{ExecError, Interpreter, LoadError, Quirks};
+
+fn run(program: &[u8], ticks: usize) -> Interpreter {
+    let mut interpreter = Interpreter::new(program.to_vec(), Quirks::xochip()).unwrap();
+    for _ in 0..ticks {
+        interpreter.tick().unwrap();
+    }
+    interpreter
+}
+
+#[test]
+fn has_64k_of_memory() {
+    // I = long ffff, V0 = 42, LD [I] v0
+    let interpreter = run(&[0xf0, 0x00, 0xff, 0xff, 0x60, 0x2a, 0xf0, 0x55], 3);
+    assert_eq!(interpreter.memory.len(), 0x10000);
+    assert_eq!(interpreter.memory[0xffff], 42);
+    assert_eq!(interpreter.pc(), 0x208);
+}
+
+#[test]
+fn large_roms_need_xochip_memory() {
+    let program = vec![0; 4000];
+    assert_eq!(
+        Interpreter::new(program.clone(), Quirks::vip()).err(),
+        Some(LoadError::RomTooLarge {
+            size: 4000,
+            max: 0xe00
+        })
+    );
+    assert!(Interpreter::new(program, Quirks::xochip()).is_ok());
+}
+
+#[test]
+fn skips_over_long_loads() {
+    // SE v0 0, I = long 1234, V1 = 1
+    let interpreter = run(&[0x30, 0x00, 0xf0, 0x00, 0x12, 0x34, 0x61, 0x01], 2);
+    assert_eq!(interpreter.pc(), 0x208);
+    assert_eq!(interpreter.registers()[1], 1);
+}
+
+#[test]
+fn other_profiles_skip_two_bytes() {
+    // SE v0 0, then F000 1234 read as two instructions: the skip lands on 1234
+    let program = vec![0x30, 0x00, 0xf0, 0x00, 0x12, 0x34];
+    let mut interpreter = Interpreter::new(program, Quirks::schip()).unwrap();
+    interpreter.tick().unwrap();
+    assert_eq!(interpreter.pc(), 0x204);
+}
+
+#[test]
+fn vip_has_no_xochip_instructions() {
+    for op_code in [[0xf0, 0x00], [0xf1, 0x01], [0x51, 0x32], [0x00, 0xd1]] {
+        let program = [&op_code[..], &[0x12, 0x34]].concat();
+        let mut interpreter = Interpreter::new(program, Quirks::vip()).unwrap();
+        assert_eq!(
+            interpreter.tick(),
+            Err(ExecError::UnknownOpcode {
+                pc: 0x200,
+                opcode: u16::from_be_bytes(op_code)
+            })
+        );
+        assert_eq!(interpreter.index(), 0);
+    }
+}
+
+#[test]
+fn saves_and_loads_register_ranges() {
+    // V1 = 1, V2 = 2, V3 = 3, I = 300, SAVE v1 v3, LOAD v6 v4
+    let program = [
+        0x61, 0x01, 0x62, 0x02, 0x63, 0x03, 0xa3, 0x00, 0x51, 0x32, 0x56, 0x43,
+    ];
+    let interpreter = run(&program, 6);
+    assert_eq!(interpreter.memory[0x300..0x303], [1, 2, 3]);
+    assert_eq!(interpreter.registers()[4..7], [3, 2, 1]);
+}
+
+#[test]
+fn draws_on_both_planes() {
+    // PLANE 3, I = font "0", DRW v0 v0 1 uses the "0" row for plane 1 and the next
+    // byte for plane 2
+    let interpreter = run(&[0xf3, 0x01, 0xa0, 0x50, 0xd0, 0x01], 3);
+    let framebuffer = interpreter.framebuffer();
+    // Rows 0 and 1 of "0" are f0 and 90
+    assert_eq!(framebuffer.pixel(0, 0), 0b11);
+    assert_eq!(framebuffer.pixel(1, 0), 0b01);
+    assert_eq!(framebuffer.pixel(4, 0), 0);
+}
+
+#[test]
+fn clear_only_touches_selected_planes() {
+    // PLANE 3, draw, PLANE 1, CLS
+    let program = [0xf3, 0x01, 0xa0, 0x50, 0xd0, 0x01, 0xf1, 0x01, 0x00, 0xe0];
+    let interpreter = run(&program, 5);
+    assert_eq!(interpreter.framebuffer().pixel(0, 0), 0b10);
+    assert_eq!(interpreter.framebuffer().pixel(1, 0), 0);
+}
+
+#[test]
+fn scrolls_up() {
+    // I = font "0", V1 = 4, DRW v0 v1 1, SCU 3
+    let program = [0xa0, 0x50, 0x61, 0x04, 0xd0, 0x11, 0x00, 0xd3];
+    let interpreter = run(&program, 4);
+    assert!(interpreter.framebuffer().get(0, 1));
+    assert!(!interpreter.framebuffer().get(0, 4));
+}
+
+#[test]
+fn loads_audio_pattern_and_pitch() {
+    // I = font "0", AUDIO, V0 = 100, PITCH v0
+    let program = [0xa0, 0x50, 0xf0, 0x02, 0x60, 0x64, 0xf0, 0x3a];
+    let interpreter = run(&program, 4);
+    assert_eq!(
+        interpreter.audio_pattern()[..5],
+        [0xf0, 0x90, 0x90, 0x90, 0xf0]
+    );
+    assert_eq!(interpreter.pitch(), 100);
+}
@@ -0,0 +1,33 @@
+use chip8_core::Assembler;
+use std::fs;
+use std::path::Path;
+
+/// `chip8 asm`: assembles `source` into `output`, or a `.ch8` file next to it.
+/// Returns the process exit code.
+pub fn run(source: &str, output: Option<&str>) -> i32 {
+    let text = match fs::read_to_string(source) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("cannot read {}: {}", source, e);
+            return 1;
+        }
+    };
+
+    let rom = match Assembler::default().assemble(&text) {
+        Ok(rom) => rom,
+        Err(e) => {
+            eprintln!("{}: {}", source, e);
+            return 1;
+        }
+    };
+    let path = output.map_or_else(|| Path::new(source).with_extension("ch8"), Into::into);
+    if path == Path::new(source) {
+        eprintln!("{} would overwrite the source, use -o", path.display());
+        return 1;
+    }
+    if let Err(e) = fs::write(&path, rom) {
+        eprintln!("cannot write {}: {}", path.display(), e);
+        return 1;
+    }
+    0
+}
@@ -0,0 +1,234 @@
+use chip8_core::audio::{DEFAULT_FREQUENCY, DEFAULT_VOLUME};
+use chip8_core::constants::FRAMES_PER_SECOND;
+use chip8_core::Quirks;
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "usage: chip8 [options] <rom>
+       chip8 disasm <rom> [-o <file>]
+       chip8 asm <source> [-o <file>]
+
+options:
+    --quirks <profile>    vip, chip48, schip or xochip (default: vip)
+    --ips <n>             instructions per second
+    --ipf <n>             instructions per frame
+    --stack-depth <n>     maximum subroutine nesting (default: from the quirks profile)
+    --vip-memory          keep the call stack and display in RAM like the VIP
+    --wav <file>          record the buzzer to a WAV file instead of playing it
+    --mute                don't play the buzzer
+    --tone <hz>           buzzer frequency (default: 440)
+    --volume <percent>    buzzer volume (default: 25)
+    --seed <n>            seed for CXNN random numbers (default: from the clock)
+    --record <movie>      record the keypad of every frame to a movie file
+    --play <movie>        play a recorded movie back, with its quirks and seed
+    --debug               start paused with a debugger prompt on the terminal
+    --gdb <addr>          wait for GDB on a port, host:port or Unix socket path
+    --rewind <seconds>    how far back Backspace can rewind, 0 to disable (default: 10)
+
+headless options:
+    --headless            run without a window and print the registers at the end
+    --frames <n>          stop after n frames (default: 600)
+    --input <script>      key events to feed, one `<frame> <key> down|up` per line
+    --dump <image>        write the final display to a .png or .pbm file
+    --every <k>           also write every kth frame, numbered after the --dump name";
+
+const DEFAULT_REWIND_SECONDS: usize = 10;
+
+/// Ten seconds
+const DEFAULT_HEADLESS_FRAMES: usize = 600;
+
+/// Roughly 700 instructions per second
+const DEFAULT_INSTRUCTIONS_PER_FRAME: usize = 11;
+
+/// What the command line asks for: running a ROM, or one of the tools
+pub enum Command {
+    Run(Box<Options>),
+    Disasm {
+        rom: String,
+        output: Option<String>,
+    },
+    /// Writes next to the source, with a `.ch8` extension, unless given an output
+    Asm {
+        source: String,
+        output: Option<String>,
+    },
+}
+
+impl Command {
+    pub fn from_args() -> Result<Self, String> {
+        let mut args = env::args().skip(1).peekable();
+        match args.peek().map(String::as_str) {
+            Some("disasm") => {
+                args.next();
+                let (rom, output) = parse_tool_args(args)?;
+                Ok(Command::Disasm { rom, output })
+            }
+            Some("asm") => {
+                args.next();
+                let (source, output) = parse_tool_args(args)?;
+                Ok(Command::Asm { source, output })
+            }
+            _ => Options::parse(args).map(|options| Command::Run(Box::new(options))),
+        }
+    }
+}
+
+pub struct Options {
+    pub rom: String,
+    pub quirks: Quirks,
+    pub instructions_per_frame: usize,
+    pub wav: Option<String>,
+    pub mute: bool,
+    pub frequency: f32,
+    pub volume: f32,
+    pub rewind_frames: usize,
+    pub seed: u64,
+    pub record: Option<String>,
+    pub play: Option<String>,
+    pub headless: bool,
+    pub debug: bool,
+    pub gdb: Option<String>,
+    pub frames: usize,
+    pub input: Option<String>,
+    pub dump: Option<String>,
+    pub every: Option<usize>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut rom = None;
+        let mut quirks = Quirks::default();
+        let mut instructions_per_frame = DEFAULT_INSTRUCTIONS_PER_FRAME;
+        // Applied on top of the profile, whatever order the options come in
+        let mut stack_depth = None;
+        let mut vip_memory_layout = false;
+        let mut wav = None;
+        let mut mute = false;
+        let mut frequency = DEFAULT_FREQUENCY;
+        let mut volume = DEFAULT_VOLUME;
+        let mut rewind_seconds = DEFAULT_REWIND_SECONDS;
+        let mut seed = None;
+        let mut record = None;
+        let mut play = None;
+        let mut headless = false;
+        let mut debug = false;
+        let mut gdb = None;
+        let mut frames = DEFAULT_HEADLESS_FRAMES;
+        let mut input = None;
+        let mut dump = None;
+        let mut every = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--quirks" => {
+                    let profile = args.next().ok_or("--quirks expects a profile name")?;
+                    quirks = profile.parse()?;
+                }
+                "--ips" => {
+                    let ips = parse_number(args.next(), "--ips")?;
+                    instructions_per_frame = (ips / FRAMES_PER_SECOND as usize).max(1);
+                }
+                "--ipf" => {
+                    instructions_per_frame = parse_number(args.next(), "--ipf")?;
+                }
+                "--stack-depth" => {
+                    stack_depth = Some(parse_number(args.next(), "--stack-depth")?);
+                }
+                "--vip-memory" => vip_memory_layout = true,
+                "--wav" => wav = Some(args.next().ok_or("--wav expects a file name")?),
+                "--mute" => mute = true,
+                "--tone" => frequency = parse_number(args.next(), "--tone")? as f32,
+                "--seed" => seed = Some(parse_number(args.next(), "--seed")? as u64),
+                "--record" => record = Some(args.next().ok_or("--record expects a file name")?),
+                "--play" => play = Some(args.next().ok_or("--play expects a file name")?),
+                "--headless" => headless = true,
+                "--debug" => debug = true,
+                "--gdb" => gdb = Some(args.next().ok_or("--gdb expects an address")?),
+                "--frames" => frames = parse_number(args.next(), "--frames")?,
+                "--input" => input = Some(args.next().ok_or("--input expects a file name")?),
+                "--dump" => {
+                    let path = args.next().ok_or("--dump expects a file name")?;
+                    if !path.ends_with(".png") && !path.ends_with(".pbm") {
+                        return Err("--dump expects a .png or .pbm file".to_string());
+                    }
+                    dump = Some(path);
+                }
+                "--every" => every = Some(parse_number(args.next(), "--every")?.max(1)),
+                "--rewind" => rewind_seconds = parse_number(args.next(), "--rewind")?,
+                "--volume" => volume = parse_number(args.next(), "--volume")? as f32 / 100.0,
+                _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+                _ => rom = Some(arg),
+            }
+        }
+
+        if record.is_some() && play.is_some() {
+            return Err("--record and --play can't be used together".to_string());
+        }
+        if debug && gdb.is_some() {
+            return Err("--debug and --gdb can't be used together".to_string());
+        }
+        if (debug || gdb.is_some()) && (headless || record.is_some() || play.is_some()) {
+            return Err(
+                "--debug and --gdb can't be used with --headless, --record or --play".to_string(),
+            );
+        }
+        if every.is_some() && dump.is_none() {
+            return Err("--every needs --dump to name the images".to_string());
+        }
+        if let Some(depth) = stack_depth {
+            quirks.stack_depth = depth;
+        }
+        quirks.vip_memory_layout |= vip_memory_layout;
+
+        Ok(Options {
+            rom: rom.ok_or(USAGE)?,
+            quirks,
+            instructions_per_frame,
+            wav,
+            mute,
+            frequency,
+            volume,
+            rewind_frames: rewind_seconds * FRAMES_PER_SECOND as usize,
+            seed: seed.unwrap_or_else(clock_seed),
+            record,
+            play,
+            headless,
+            debug,
+            gdb,
+            frames,
+            input,
+            dump,
+            every,
+        })
+    }
+}
+
+/// `<input> [-o <output>]`
+fn parse_tool_args(
+    mut args: impl Iterator<Item = String>,
+) -> Result<(String, Option<String>), String> {
+    let mut input = None;
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(args.next().ok_or("-o expects a file name")?),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if input.is_some() => return Err(USAGE.to_string()),
+            _ => input = Some(arg),
+        }
+    }
+    Ok((input.ok_or(USAGE)?, output))
+}
+
+fn clock_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos() as u64)
+}
+
+fn parse_number(value: Option<String>, option: &str) -> Result<usize, String> {
+    value
+        .ok_or(format!("{} expects a number", option))?
+        .parse()
+        .map_err(|e| format!("invalid value for {}: {}", option, e))
+}
@@ -0,0 +1,239 @@
+use crate::cli::Options;
+use chip8_core::{
+    Command, DebugEvent, Debugger, GdbStub, Interpreter, InterpreterResult, MovieError,
+};
+use std::io::{self, BufRead, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use winit::event_loop::EventLoopProxy;
+
+const PROMPT: &str = "(chip8) ";
+
+/// Debugger input, read on other threads and handed to the event loop so the
+/// window keeps running while waiting for it
+pub enum DebugInput {
+    Line(String),
+    Gdb(Vec<u8>),
+    Disconnected,
+}
+
+/// Whether execution is driven by the terminal debugger or a GDB client
+pub enum DebugMode {
+    Off,
+    Terminal(Debugger),
+    Gdb {
+        stub: GdbStub,
+        client: Box<dyn Write>,
+    },
+}
+
+impl DebugMode {
+    /// Starts the terminal prompt, or waits for a GDB client to connect
+    pub fn start(options: &Options, proxy: EventLoopProxy<DebugInput>) -> Result<Self, String> {
+        if options.debug {
+            spawn_repl(proxy);
+            return Ok(DebugMode::Terminal(Debugger::new()));
+        }
+        match &options.gdb {
+            Some(addr) => {
+                let (reader, client) = accept(addr).map_err(|e| format!("gdb {}: {}", addr, e))?;
+                spawn_reader(reader, proxy);
+                Ok(DebugMode::Gdb {
+                    stub: GdbStub::new(),
+                    client,
+                })
+            }
+            None => Ok(DebugMode::Off),
+        }
+    }
+
+    pub fn is_off(&self) -> bool {
+        matches!(self, DebugMode::Off)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        match self {
+            DebugMode::Off => false,
+            DebugMode::Terminal(debugger) => debugger.is_paused(),
+            DebugMode::Gdb { stub, .. } => stub.is_paused(),
+        }
+    }
+
+    pub fn handle(&mut self, input: DebugInput, interpreter: &mut Interpreter) {
+        match (self, input) {
+            (DebugMode::Terminal(debugger), DebugInput::Line(line)) => {
+                run_command(debugger, interpreter, &line)
+            }
+            (DebugMode::Gdb { stub, client }, DebugInput::Gdb(bytes)) => {
+                stub.receive(&bytes, interpreter);
+                flush(stub, client);
+            }
+            (DebugMode::Gdb { stub, .. }, DebugInput::Disconnected) => {
+                log::info!("GDB disconnected");
+                stub.detach();
+            }
+            _ => (),
+        }
+    }
+
+    /// Runs a frame unless paused, reporting breakpoints and crashes to the terminal
+    /// or the GDB client
+    pub fn run_frame(
+        &mut self,
+        interpreter: &mut Interpreter,
+        keys: u16,
+        instructions_per_frame: usize,
+    ) -> Result<Option<InterpreterResult>, MovieError> {
+        interpreter.set_keys(keys);
+        let event = match self {
+            DebugMode::Off => Ok(DebugEvent::Ran(
+                interpreter.run_frame(instructions_per_frame)?,
+            )),
+            DebugMode::Terminal(debugger) => {
+                let event = debugger.run_frame(interpreter, instructions_per_frame);
+                match &event {
+                    Ok(DebugEvent::Breakpoint(addr)) => {
+                        println!("\nbreakpoint at {:03x}", addr);
+                        run_command(debugger, interpreter, "dis");
+                    }
+                    Ok(DebugEvent::Watchpoint(hit)) => {
+                        println!("\nwatchpoint: {}", hit);
+                        run_command(debugger, interpreter, "dis");
+                    }
+                    Err(e) => {
+                        println!("\n{}", e);
+                        run_command(debugger, interpreter, "pause");
+                    }
+                    Ok(_) => (),
+                }
+                event
+            }
+            DebugMode::Gdb { stub, client } => {
+                let event = stub.run_frame(interpreter, instructions_per_frame);
+                flush(stub, client);
+                event
+            }
+        };
+
+        let result = match event? {
+            DebugEvent::Ran(result) => result,
+            DebugEvent::Paused => InterpreterResult {
+                refresh_display: false,
+                wait_for_keyboard: None,
+            },
+            DebugEvent::Breakpoint(_) | DebugEvent::Watchpoint(_) => InterpreterResult {
+                refresh_display: true,
+                wait_for_keyboard: None,
+            },
+        };
+        Ok(Some(result))
+    }
+}
+
+/// Reads debugger commands from stdin
+fn spawn_repl(proxy: EventLoopProxy<DebugInput>) {
+    println!("Paused at startup, type help for the list of commands");
+    prompt();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            if proxy.send_event(DebugInput::Line(line)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn run_command(debugger: &mut Debugger, interpreter: &mut Interpreter, line: &str) {
+    if !line.trim().is_empty() {
+        let output = line
+            .parse::<Command>()
+            .and_then(|command| {
+                debugger
+                    .execute(command, interpreter)
+                    .map_err(|e| e.to_string())
+            })
+            .unwrap_or_else(|e| e);
+        println!("{}", output);
+    }
+    prompt();
+}
+
+fn prompt() {
+    print!("{}", PROMPT);
+    let _ = io::stdout().flush();
+}
+
+/// Waits for a GDB client on `addr`: a port or `host:port` for TCP, anything else
+/// is the path of a Unix socket
+fn accept(addr: &str) -> io::Result<(Box<dyn Read + Send>, Box<dyn Write>)> {
+    let tcp_addr = match addr.rsplit_once(':') {
+        Some((_, port)) if port.parse::<u16>().is_ok() => Some(addr.to_string()),
+        _ if addr.parse::<u16>().is_ok() => Some(format!("127.0.0.1:{}", addr)),
+        _ => None,
+    };
+
+    if let Some(tcp_addr) = tcp_addr {
+        let listener = TcpListener::bind(&tcp_addr)?;
+        println!("Waiting for GDB on {}", listener.local_addr()?);
+        let (stream, peer) = listener.accept()?;
+        log::info!("GDB connected from {}", peer);
+        stream.set_nodelay(true)?;
+        return Ok((Box::new(stream.try_clone()?), Box::new(stream)));
+    }
+    accept_unix(addr)
+}
+
+#[cfg(unix)]
+fn accept_unix(path: &str) -> io::Result<(Box<dyn Read + Send>, Box<dyn Write>)> {
+    use std::fs;
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixListener;
+
+    // A socket left behind by a previous run would make binding fail
+    if fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    println!("Waiting for GDB on {}", path);
+    let (stream, _) = listener.accept()?;
+    log::info!("GDB connected");
+    Ok((Box::new(stream.try_clone()?), Box::new(stream)))
+}
+
+#[cfg(not(unix))]
+fn accept_unix(_path: &str) -> io::Result<(Box<dyn Read + Send>, Box<dyn Write>)> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Unix sockets are not available, use a TCP port",
+    ))
+}
+
+/// Forwards whatever the GDB client sends
+fn spawn_reader(mut reader: Box<dyn Read + Send>, proxy: EventLoopProxy<DebugInput>) {
+    thread::spawn(move || {
+        let mut buffer = [0; 4096];
+        loop {
+            let input = match reader.read(&mut buffer) {
+                Ok(0) | Err(_) => DebugInput::Disconnected,
+                Ok(length) => DebugInput::Gdb(buffer[..length].to_vec()),
+            };
+            let disconnected = matches!(input, DebugInput::Disconnected);
+            if proxy.send_event(input).is_err() || disconnected {
+                break;
+            }
+        }
+    });
+}
+
+fn flush(stub: &mut GdbStub, client: &mut Box<dyn Write>) {
+    let output = stub.take_output();
+    if !output.is_empty() {
+        if let Err(e) = client.write_all(&output).and_then(|_| client.flush()) {
+            log::error!("Cannot write to GDB: {}", e);
+        }
+    }
+}
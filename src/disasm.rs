@@ -0,0 +1,26 @@
+use chip8_core::Disassembler;
+use std::fs;
+
+/// `chip8 disasm`: writes the listing of a ROM to `output`, or stdout. Returns the
+/// process exit code.
+pub fn run(rom: &str, output: Option<&str>) -> i32 {
+    let program = match fs::read(rom) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("cannot read {}: {}", rom, e);
+            return 1;
+        }
+    };
+
+    let listing = Disassembler::default().listing(&program);
+    match output {
+        Some(path) => {
+            if let Err(e) = fs::write(path, listing) {
+                eprintln!("cannot write {}: {}", path, e);
+                return 1;
+            }
+        }
+        None => print!("{}", listing),
+    }
+    0
+}
@@ -0,0 +1,163 @@
+use crate::cli::Options;
+use crate::movie::MovieMode;
+use crate::{png, PALETTE};
+use chip8_core::{decode_at, ExecutionState, Framebuffer, Instruction, Interpreter};
+use std::fs;
+
+/// Key presses and releases to feed the interpreter, read from a text file with one
+/// `<frame> <key> down|up` event per line. Lines starting with `#` are comments.
+#[derive(Default)]
+struct Script {
+    events: Vec<(usize, u8, bool)>,
+}
+
+impl Script {
+    fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        let mut events = Vec::new();
+        for (k, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let event = match fields[..] {
+                [frame, key, action] => frame.parse().ok().zip(
+                    u8::from_str_radix(key, 16)
+                        .ok()
+                        .filter(|&key| key < 16)
+                        .zip(match action {
+                            "down" => Some(true),
+                            "up" => Some(false),
+                            _ => None,
+                        }),
+                ),
+                _ => None,
+            };
+            let (frame, (key, pressed)) = event.ok_or(format!(
+                "{}:{}: expected <frame> <key> down|up",
+                path,
+                k + 1
+            ))?;
+            events.push((frame, key, pressed));
+        }
+        events.sort_by_key(|&(frame, ..)| frame);
+        Ok(Script { events })
+    }
+
+    /// Keypad bitmask for `frame`, given the one of the previous frame
+    fn keys_at(&self, frame: usize, mut keys: u16) -> u16 {
+        for &(_, key, pressed) in self.events.iter().filter(|event| event.0 == frame) {
+            if pressed {
+                keys |= 1 << key;
+            } else {
+                keys &= !(1 << key);
+            }
+        }
+        keys
+    }
+}
+
+/// Runs without a window until the frame limit, a halt or a crash, then prints the
+/// registers. Returns the process exit code.
+pub fn run(
+    options: &Options,
+    mut interpreter: Interpreter,
+    mut movie: MovieMode,
+    instructions_per_frame: usize,
+) -> i32 {
+    let script = match options.input.as_deref().map(Script::load).transpose() {
+        Ok(script) => script.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+
+    let mut keys = 0;
+    let mut frame = 0;
+    let (reason, code) = loop {
+        if frame == options.frames {
+            break ("frame limit reached".to_string(), 0);
+        }
+        keys = script.keys_at(frame, keys);
+        match movie.run_frame(&mut interpreter, keys, instructions_per_frame) {
+            Ok(Some(_)) => (),
+            Ok(None) => break ("movie finished".to_string(), 0),
+            Err(e) => break (e.to_string(), 1),
+        }
+        frame += 1;
+
+        if let (Some(path), Some(every)) = (&options.dump, options.every) {
+            if frame % every == 0 {
+                write_image(&numbered(path, frame), interpreter.framebuffer());
+            }
+        }
+        if interpreter.state() == ExecutionState::Exited {
+            break ("program exited".to_string(), 0);
+        }
+        if is_halted(&interpreter) {
+            break ("program halted".to_string(), 0);
+        }
+    };
+
+    movie.save();
+    if let Some(path) = &options.dump {
+        write_image(path, interpreter.framebuffer());
+    }
+    print!("{}", dump_registers(&interpreter, frame, &reason));
+    code
+}
+
+/// Whether the program is stuck on a jump to itself, the usual way to end a ROM
+fn is_halted(interpreter: &Interpreter) -> bool {
+    let pc = interpreter.pc();
+    decode_at(&interpreter.memory, pc as usize) == Instruction::Jp(pc)
+}
+
+/// `out.png` becomes `out-000120.png` for frame 120
+fn numbered(path: &str, frame: usize) -> String {
+    match path.rsplit_once('.') {
+        Some((stem, extension)) => format!("{}-{:06}.{}", stem, frame, extension),
+        None => format!("{}-{:06}", path, frame),
+    }
+}
+
+fn write_image(path: &str, framebuffer: &Framebuffer) {
+    let image = if path.ends_with(".pbm") {
+        framebuffer.to_pbm().into_bytes()
+    } else {
+        png::encode(framebuffer, &PALETTE)
+    };
+    if let Err(e) = fs::write(path, image) {
+        log::error!("Cannot write {}: {}", path, e);
+    }
+}
+
+fn dump_registers(interpreter: &Interpreter, frame: usize, reason: &str) -> String {
+    let registers: Vec<String> = interpreter
+        .registers()
+        .iter()
+        .enumerate()
+        .map(|(x, value)| format!("v{:x}: {:02x}", x, value))
+        .collect();
+    let stack: Vec<String> = interpreter
+        .stack()
+        .frames()
+        .iter()
+        .map(|addr| format!("{:03x}", addr))
+        .collect();
+
+    format!(
+        "stopped: {} after {} frames\npc: {:03x}  i: {:03x}\n{}\n{}\ndt: {}  st: {}\nstack: [{}]\n",
+        reason,
+        frame,
+        interpreter.pc(),
+        interpreter.index(),
+        registers[..8].join("  "),
+        registers[8..].join("  "),
+        interpreter.delay_timer(),
+        interpreter.sound_timer(),
+        stack.join(", ")
+    )
+}
@@ -1,28 +1,94 @@
-mod constants;
-mod disassembler;
-mod interpreter;
-mod util;
-
-use crate::constants::DISPLAY_MEM_START;
-use crate::interpreter::Interpreter;
-use crate::util::{get_bit_at, key_to_chip_8};
+mod asm;
+mod cli;
+mod debug;
+mod disasm;
+mod headless;
+mod keyboard;
+mod movie;
+mod png;
+mod speaker;
+
+use crate::cli::Command;
+use crate::debug::DebugMode;
+use crate::keyboard::key_to_chip_8;
+use crate::movie::MovieMode;
+use crate::speaker::Speaker;
+use chip8_core::constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH, FRAMES_PER_SECOND};
+use chip8_core::{Buzzer, ExecutionState, Framebuffer, Interpreter, MovieError, Rewind, WavSink};
 use pixels::{Error, Pixels, SurfaceTexture};
 use std::time::{Duration, Instant};
-use std::{env, fs};
+use std::{fs, process};
 use winit::dpi::LogicalSize;
-use winit::event::{ElementState, Event, KeyboardInput, StartCause, VirtualKeyCode, WindowEvent};
+use winit::event::{
+    ElementState, Event, KeyboardInput, ModifiersState, StartCause, VirtualKeyCode, WindowEvent,
+};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::WindowBuilder;
 
-const WIDTH: u32 = 64;
-const HEIGHT: u32 = 32;
+const WIDTH: u32 = DISPLAY_WIDTH as u32;
+const HEIGHT: u32 = DISPLAY_HEIGHT as u32;
 
 const WHITE: [u8; 4] = [0xfb, 0xbb, 0xb3, 0xff];
 const BLACK: [u8; 4] = [0x88, 0xaa, 0x88, 0xff];
+// Only XO-CHIP programs draw on the second plane
+const PLANE_2_COLOR: [u8; 4] = [0x4c, 0x6c, 0x5c, 0xff];
+const BOTH_PLANES_COLOR: [u8; 4] = [0x22, 0x33, 0x2a, 0xff];
+
+/// Indexed by the framebuffer's color index
+const PALETTE: [[u8; 4]; 4] = [BLACK, WHITE, PLANE_2_COLOR, BOTH_PLANES_COLOR];
 
 fn main() -> Result<(), Error> {
     env_logger::init();
-    let event_loop = EventLoop::new();
+    let options = match Command::from_args() {
+        Ok(Command::Run(options)) => *options,
+        Ok(Command::Disasm { rom, output }) => process::exit(disasm::run(&rom, output.as_deref())),
+        Ok(Command::Asm { source, output }) => process::exit(asm::run(&source, output.as_deref())),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    let buffer = fs::read(&options.rom).unwrap_or_else(|e| {
+        eprintln!("cannot read {}: {}", options.rom, e);
+        process::exit(1);
+    });
+
+    let (mut interpreter, mut movie) = MovieMode::start(&options, buffer).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    let instructions_per_frame = match &movie {
+        MovieMode::Playing { movie, .. } => movie.instructions_per_frame,
+        _ => options.instructions_per_frame,
+    };
+    if let Some(path) = &options.wav {
+        let buzzer = Buzzer::default()
+            .with_frequency(options.frequency)
+            .with_volume(options.volume);
+        let sink = WavSink::create(path, buzzer).unwrap_or_else(|e| {
+            eprintln!("cannot create {}: {}", path, e);
+            process::exit(1);
+        });
+        interpreter.set_audio_sink(Box::new(sink));
+    }
+
+    if options.headless {
+        process::exit(headless::run(
+            &options,
+            interpreter,
+            movie,
+            instructions_per_frame,
+        ));
+    }
+    if options.wav.is_none() && !options.mute {
+        match Speaker::new(options.frequency, options.volume) {
+            Ok(speaker) => interpreter.set_audio_sink(Box::new(speaker)),
+            Err(e) => log::warn!("Sound is off: {}", e),
+        }
+    }
+
+    let event_loop = EventLoop::with_user_event();
     let window = {
         let size = LogicalSize::new((WIDTH * 12) as f64, (HEIGHT * 12) as f64);
         WindowBuilder::new()
@@ -39,24 +105,34 @@ fn main() -> Result<(), Error> {
         Pixels::new(WIDTH, HEIGHT, surface_texture)?
     };
 
-    let args: Vec<String> = env::args().collect();
-    let filename = &args[1];
-
-    let buffer = fs::read(filename).unwrap();
+    let frame_length = Duration::from_secs(1) / FRAMES_PER_SECOND;
+    let mut next_frame = Instant::now();
+    let mut buffer_size = (WIDTH, HEIGHT);
+    let mut modifiers = ModifiersState::empty();
+    let mut rewind = Rewind::new(options.rewind_frames);
+    let mut rewinding = false;
+    // Keypad bitmask, handed to the interpreter at the start of every frame
+    let mut keys = 0u16;
 
-    let mut interpreter = Interpreter::new(buffer);
-
-    let timer_length = Duration::new(0, 16);
-
-    interpreter.disassemble_program();
-
-    let mut register_to_store: usize = 0;
-    let mut waiting_for_key = false;
+    let mut debug = match DebugMode::start(&options, event_loop.create_proxy()) {
+        Ok(debug) => debug,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
 
     event_loop.run(move |event, _, control_flow| {
         match event {
             Event::RedrawRequested(_) => {
-                draw(pixels.get_frame(), &interpreter.memory);
+                let framebuffer = interpreter.framebuffer();
+                // SUPER-CHIP programs can switch resolution at any time
+                let size = (framebuffer.width() as u32, framebuffer.height() as u32);
+                if size != buffer_size {
+                    pixels.resize_buffer(size.0, size.1);
+                    buffer_size = size;
+                }
+                draw(pixels.get_frame(), framebuffer);
                 if pixels
                     .render()
                     .map_err(|e| panic!("pixels.render() failed: {:?}", e))
@@ -66,29 +142,85 @@ fn main() -> Result<(), Error> {
                 }
             }
             Event::NewEvents(StartCause::Init) => {
-                *control_flow = ControlFlow::WaitUntil(Instant::now() + timer_length);
+                next_frame = Instant::now() + frame_length;
+                *control_flow = ControlFlow::WaitUntil(next_frame);
+            }
+            Event::LoopDestroyed => movie.save(),
+            Event::UserEvent(input) => {
+                debug.handle(input, &mut interpreter);
+                window.request_redraw();
+                // Continuing after a crash has to restart the frame schedule
+                if !debug.is_paused() && *control_flow == ControlFlow::Wait {
+                    next_frame = Instant::now() + frame_length;
+                    *control_flow = ControlFlow::WaitUntil(next_frame);
+                }
+            }
+            Event::NewEvents(StartCause::ResumeTimeReached { .. }) if debug.is_paused() => {
+                next_frame = next_deadline(next_frame, frame_length);
+                *control_flow = ControlFlow::WaitUntil(next_frame);
+            }
+            Event::NewEvents(StartCause::ResumeTimeReached { .. }) if rewinding => {
+                if let Some(state) = rewind.step_back() {
+                    if let Err(e) = interpreter.load_state(state) {
+                        log::error!("Cannot rewind: {}", e);
+                        rewind.clear();
+                    }
+                    window.request_redraw();
+                }
+
+                next_frame = next_deadline(next_frame, frame_length);
+                *control_flow = ControlFlow::WaitUntil(next_frame);
             }
             Event::NewEvents(StartCause::ResumeTimeReached { .. }) => {
-                let result = interpreter.tick();
+                let frame = if debug.is_off() {
+                    movie.run_frame(&mut interpreter, keys, instructions_per_frame)
+                } else {
+                    debug.run_frame(&mut interpreter, keys, instructions_per_frame)
+                };
+                let result = match frame {
+                    Ok(Some(result)) => result,
+                    Ok(None) => {
+                        log::info!("Movie finished");
+                        window.set_title("CHIP-8 - movie finished");
+                        *control_flow = ControlFlow::Wait;
+                        return;
+                    }
+                    Err(MovieError::Exec(e)) => {
+                        // Keep the last frame on screen so the crash can be inspected
+                        log::error!("Interpreter crashed: {}", e);
+                        window.set_title(&format!("CHIP-8 - crashed: {}", e));
+                        *control_flow = ControlFlow::Wait;
+                        return;
+                    }
+                    Err(e) => {
+                        log::error!("Playback stopped: {}", e);
+                        window.set_title(&format!("CHIP-8 - {}", e));
+                        *control_flow = ControlFlow::Wait;
+                        return;
+                    }
+                };
+
+                if interpreter.state() == ExecutionState::Exited {
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
+                if rewind.capacity() > 0 {
+                    rewind.record(interpreter.save_state());
+                }
 
                 if result.refresh_display {
                     // Request a redraw
                     window.request_redraw();
                 }
-                *control_flow = match result.wait_for_keyboard {
-                    Some(register) => {
-                        println!("Waiting!!");
-                        register_to_store = register;
-                        waiting_for_key = true;
-                        ControlFlow::Wait
-                    }
-                    None => ControlFlow::WaitUntil(Instant::now() + timer_length),
-                };
+
+                next_frame = next_deadline(next_frame, frame_length);
+                *control_flow = ControlFlow::WaitUntil(next_frame);
             }
             Event::WindowEvent { event, .. } => match event {
                 WindowEvent::CloseRequested => {
                     *control_flow = ControlFlow::Exit;
                 }
+                WindowEvent::ModifiersChanged(state) => modifiers = state,
                 WindowEvent::KeyboardInput {
                     input:
                         KeyboardInput {
@@ -101,6 +233,25 @@ fn main() -> Result<(), Error> {
                     VirtualKeyCode::Escape => {
                         *control_flow = ControlFlow::Exit;
                     }
+                    // Going back in time would break the movie
+                    VirtualKeyCode::Back if movie.is_off() => {
+                        rewinding = state == ElementState::Pressed
+                    }
+                    VirtualKeyCode::F1
+                    | VirtualKeyCode::F2
+                    | VirtualKeyCode::F3
+                    | VirtualKeyCode::F4
+                        if state == ElementState::Pressed =>
+                    {
+                        let slot = state_slot(&options.rom, virtual_code);
+                        if modifiers.shift() {
+                            save_state(&interpreter, &slot);
+                        } else if !movie.is_off() {
+                            log::warn!("Save states can't be loaded during a movie");
+                        } else if load_state(&mut interpreter, &slot) {
+                            window.request_redraw();
+                        }
+                    }
                     VirtualKeyCode::Key1
                     | VirtualKeyCode::Key2
                     | VirtualKeyCode::Key3
@@ -117,14 +268,11 @@ fn main() -> Result<(), Error> {
                     | VirtualKeyCode::X
                     | VirtualKeyCode::C
                     | VirtualKeyCode::V => {
-                        let pressed = state == ElementState::Pressed;
-                        interpreter.press_key(key_to_chip_8(virtual_code), pressed);
-                        if waiting_for_key && state == ElementState::Released {
-                            interpreter.store_key(register_to_store, key_to_chip_8(virtual_code));
-                            waiting_for_key = false;
-                        }
-                        if *control_flow == ControlFlow::Wait {
-                            *control_flow = ControlFlow::WaitUntil(Instant::now() + timer_length);
+                        let key = key_to_chip_8(virtual_code);
+                        if state == ElementState::Pressed {
+                            keys |= 1 << key;
+                        } else {
+                            keys &= !(1 << key);
                         }
                     }
                     _ => (),
@@ -136,13 +284,43 @@ fn main() -> Result<(), Error> {
     });
 }
 
-fn draw(frame: &mut [u8], memory: &[u8; 4096]) {
-    for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
-        let color = match get_bit_at(memory[DISPLAY_MEM_START + i / 8], (i % 8) as u8) {
-            true => WHITE,
-            false => BLACK,
-        };
+/// Schedules from the previous deadline so frames don't drift
+fn next_deadline(previous: Instant, frame_length: Duration) -> Instant {
+    (previous + frame_length).max(Instant::now())
+}
+
+/// Save state file for the F1-F4 slot, next to the ROM
+fn state_slot(rom: &str, key: VirtualKeyCode) -> String {
+    let slot = match key {
+        VirtualKeyCode::F1 => 1,
+        VirtualKeyCode::F2 => 2,
+        VirtualKeyCode::F3 => 3,
+        _ => 4,
+    };
+    format!("{}.state{}", rom, slot)
+}
+
+fn save_state(interpreter: &Interpreter, path: &str) {
+    match fs::write(path, interpreter.save_state()) {
+        Ok(()) => log::info!("Saved state to {}", path),
+        Err(e) => log::error!("Cannot save state to {}: {}", path, e),
+    }
+}
+
+fn load_state(interpreter: &mut Interpreter, path: &str) -> bool {
+    let result = fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| interpreter.load_state(&bytes).map_err(|e| e.to_string()));
+    match result {
+        Ok(()) => log::info!("Loaded state from {}", path),
+        Err(ref e) => log::error!("Cannot load state from {}: {}", path, e),
+    }
+    result.is_ok()
+}
 
-        pixel.copy_from_slice(&color);
+fn draw(frame: &mut [u8], framebuffer: &Framebuffer) {
+    let pixels = framebuffer.rows().flat_map(|row| row.iter());
+    for (pixel, &color) in frame.chunks_exact_mut(4).zip(pixels) {
+        pixel.copy_from_slice(&PALETTE[color as usize & 0b11]);
     }
 }
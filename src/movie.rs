@@ -0,0 +1,85 @@
+use crate::cli::Options;
+use chip8_core::{Interpreter, InterpreterResult, Movie, MovieError};
+use std::fs;
+
+/// Whether the frontend is recording or playing back an input movie
+pub enum MovieMode {
+    Off,
+    Recording { movie: Movie, path: String },
+    Playing { movie: Movie, frame: usize },
+}
+
+impl MovieMode {
+    /// Creates the interpreter, taking the quirks and seed from the movie when
+    /// playing one back
+    pub fn start(options: &Options, program: Vec<u8>) -> Result<(Interpreter, Self), String> {
+        if let Some(path) = &options.play {
+            let movie: Movie = fs::read_to_string(path)
+                .map_err(|e| format!("cannot read {}: {}", path, e))?
+                .parse()
+                .map_err(|e| format!("cannot play {}: {}", path, e))?;
+            let interpreter = movie
+                .interpreter(program)
+                .map_err(|e| format!("cannot play {}: {}", path, e))?;
+            return Ok((interpreter, MovieMode::Playing { movie, frame: 0 }));
+        }
+
+        let mut interpreter = Interpreter::new(program.clone(), options.quirks)
+            .map_err(|e| format!("cannot load {}: {}", options.rom, e))?;
+        log::info!("Random seed: {}", options.seed);
+        interpreter.set_seed(options.seed);
+
+        let mode = match &options.record {
+            Some(path) => MovieMode::Recording {
+                movie: Movie::new(
+                    &program,
+                    options.quirks,
+                    options.seed,
+                    options.instructions_per_frame,
+                ),
+                path: path.clone(),
+            },
+            None => MovieMode::Off,
+        };
+        Ok((interpreter, mode))
+    }
+
+    pub fn is_off(&self) -> bool {
+        matches!(self, MovieMode::Off)
+    }
+
+    /// Runs a frame with the keys held on the keyboard, or the movie's when playing.
+    /// Returns `None` once playback is over.
+    pub fn run_frame(
+        &mut self,
+        interpreter: &mut Interpreter,
+        keys: u16,
+        instructions_per_frame: usize,
+    ) -> Result<Option<InterpreterResult>, MovieError> {
+        match self {
+            MovieMode::Off => {
+                interpreter.set_keys(keys);
+                Ok(Some(interpreter.run_frame(instructions_per_frame)?))
+            }
+            MovieMode::Recording { movie, .. } => {
+                Ok(Some(movie.record_frame(interpreter, keys, true)?))
+            }
+            MovieMode::Playing { movie, frame } if *frame < movie.len() => {
+                let result = movie.play_frame(interpreter, *frame)?;
+                *frame += 1;
+                Ok(Some(result))
+            }
+            MovieMode::Playing { .. } => Ok(None),
+        }
+    }
+
+    /// Writes the movie being recorded
+    pub fn save(&self) {
+        if let MovieMode::Recording { movie, path } = self {
+            match fs::write(path, movie.to_string()) {
+                Ok(()) => log::info!("Saved {} frames to {}", movie.len(), path),
+                Err(e) => log::error!("Cannot save movie to {}: {}", path, e),
+            }
+        }
+    }
+}
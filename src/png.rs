@@ -0,0 +1,82 @@
+//! Just enough of PNG to save the display: an indexed color image whose pixel data
+//! is stored in uncompressed deflate blocks.
+
+use chip8_core::Framebuffer;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Largest length of an uncompressed deflate block
+const MAX_BLOCK_LENGTH: usize = 0xffff;
+
+pub fn encode(framebuffer: &Framebuffer, palette: &[[u8; 4]; 4]) -> Vec<u8> {
+    let mut png = SIGNATURE.to_vec();
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&(framebuffer.width() as u32).to_be_bytes());
+    header.extend_from_slice(&(framebuffer.height() as u32).to_be_bytes());
+    // 8 bits per pixel, indexed color, default compression, filter and interlacing
+    header.extend_from_slice(&[8, 3, 0, 0, 0]);
+    write_chunk(&mut png, b"IHDR", &header);
+
+    let colors: Vec<u8> = palette
+        .iter()
+        .flat_map(|color| color[..3].to_vec())
+        .collect();
+    write_chunk(&mut png, b"PLTE", &colors);
+
+    // Every row starts with its filter type, 0 for none
+    let mut pixels = Vec::new();
+    for row in framebuffer.rows() {
+        pixels.push(0);
+        pixels.extend(row.iter().map(|&color| color & 0b11));
+    }
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&pixels));
+
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32K window and no preset dictionary
+    let mut zlib = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = data.chunks(MAX_BLOCK_LENGTH).collect();
+    for (k, block) in blocks.iter().enumerate() {
+        let last = k == blocks.len() - 1;
+        zlib.push(last as u8);
+        let length = block.len() as u16;
+        zlib.extend_from_slice(&length.to_le_bytes());
+        zlib.extend_from_slice(&(!length).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(data).to_be_bytes());
+    zlib
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    b << 16 | a
+}
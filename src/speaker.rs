@@ -0,0 +1,87 @@
+//! Plays the buzzer on the default output device.
+
+use chip8_core::{AudioFrame, AudioSink, Buzzer};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Device, FromSample, SampleFormat, SizedSample, Stream, StreamConfig};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Frames of sound queued for the device at most. The emulator and the sound card
+/// don't share a clock, so samples past this are dropped rather than let the delay
+/// grow.
+const MAX_QUEUED_FRAMES: usize = 3;
+
+pub struct Speaker {
+    // The device stops playing when the stream is dropped
+    _stream: Stream,
+    buzzer: Buzzer,
+    /// Mono samples waiting for the device
+    queue: Arc<Mutex<VecDeque<f32>>>,
+}
+
+impl Speaker {
+    pub fn new(frequency: f32, volume: f32) -> Result<Self, String> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("no audio output device")?;
+        let supported = device
+            .default_output_config()
+            .map_err(|e| e.to_string())?;
+        let config = supported.config();
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, queue.clone()),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, queue.clone()),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, queue.clone()),
+            format => return Err(format!("unsupported sample format {}", format)),
+        }?;
+        stream.play().map_err(|e| e.to_string())?;
+
+        Ok(Speaker {
+            _stream: stream,
+            buzzer: Buzzer::new(config.sample_rate.0)
+                .with_frequency(frequency)
+                .with_volume(volume),
+            queue,
+        })
+    }
+}
+
+impl AudioSink for Speaker {
+    fn play(&mut self, frame: &AudioFrame) {
+        let samples = self.buzzer.render(frame);
+        let max = MAX_QUEUED_FRAMES * self.buzzer.samples_per_frame();
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.extend(samples);
+            let excess = queue.len().saturating_sub(max);
+            queue.drain(..excess);
+        }
+    }
+}
+
+/// Copies the queued samples to every channel, with silence when the queue runs dry
+fn build_stream<T: SizedSample + FromSample<f32>>(
+    device: &Device,
+    config: &StreamConfig,
+    queue: Arc<Mutex<VecDeque<f32>>>,
+) -> Result<Stream, String> {
+    let channels = config.channels as usize;
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _| {
+                let mut queue = queue.lock().ok();
+                for frame in data.chunks_mut(channels) {
+                    let sample = queue
+                        .as_mut()
+                        .and_then(|queue| queue.pop_front())
+                        .unwrap_or(0.0);
+                    frame.fill(T::from_sample(sample));
+                }
+            },
+            |e| log::warn!("audio stream error: {}", e),
+            None,
+        )
+        .map_err(|e| e.to_string())
+}